@@ -0,0 +1,327 @@
+//! Functions for inspecting the current thread's garbage-collected
+//! heap, for debugging and testing.
+//!
+//! The functions in this module keep the collector borrowed while they
+//! run, so the callbacks passed to them must not allocate `Gc`s or
+//! trigger a collection.
+
+use crate::Trace;
+use crate::gc::{self, GcBox};
+use std::collections::{HashMap, VecDeque};
+use std::mem;
+
+/// Calls `f` with the value of every object on the current thread's
+/// heap that has not been collected yet.
+///
+/// This includes objects that are unreachable but have not been found
+/// by a collection yet. Values currently borrowed through
+/// [`Gc::get_mut`](crate::Gc::get_mut) are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, Trace};
+/// use std::any::TypeId;
+///
+/// let _x = Gc::new(1u32);
+/// let mut count = 0;
+/// gc::debug::for_each_live(|value| {
+///     if value.gc_type_id() == TypeId::of::<u32>() {
+///         count += 1;
+///     }
+/// });
+/// assert_eq!(count, 1);
+/// ```
+pub fn for_each_live(mut f: impl FnMut(&(dyn Trace + 'static))) {
+    gc::for_each_box(|gcbox, _| {
+        if !gcbox.header().is_borrowed() {
+            f(gcbox.value());
+        }
+    });
+}
+
+/// Returns the number of bytes currently allocated for objects on the
+/// current thread's heap.
+#[must_use]
+pub fn heap_size() -> usize {
+    gc::stats().bytes_allocated
+}
+
+/// Returns the number of live objects and the bytes allocated for them
+/// on the current thread's heap, for each type of value.
+///
+/// The bytes are counted the same way as in [`heap_size`], including
+/// the [`Trace::heap_size`] each object reported when it was allocated,
+/// so they add up to it.
+///
+/// Types are keyed by their [`std::any::type_name`]. As with
+/// [`for_each_live`], unreachable objects are counted until a
+/// collection frees them. The counts are computed by walking the heap,
+/// so this takes time proportional to the number of objects.
+///
+/// # Examples
+///
+/// ```
+/// use gc::Gc;
+///
+/// gc::force_collect();
+/// let _values = (Gc::new(1u16), Gc::new(2u16), Gc::new("three"));
+/// let stats = gc::debug::stats_by_type();
+/// assert_eq!(stats["u16"].0, 2);
+/// assert_eq!(stats["&str"].0, 1);
+/// ```
+#[must_use]
+pub fn stats_by_type() -> HashMap<&'static str, (usize, usize)> {
+    let mut stats = HashMap::new();
+    // Only the type and size are looked at, so borrowed objects are
+    // counted too.
+    gc::for_each_box(|gcbox, heap_size| {
+        let (count, bytes) = stats.entry(gcbox.value().gc_type_name()).or_insert((0, 0));
+        *count += 1;
+        *bytes += mem::size_of_val(gcbox) + heap_size;
+    });
+    stats
+}
+
+/// The result of [`mark_dry_run`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarkReport {
+    /// The number of objects reachable from a root.
+    pub reachable: usize,
+    /// The number of objects the next collection would find
+    /// unreachable.
+    pub unreachable: usize,
+}
+
+/// Runs the mark phase of a collection on the current thread, without
+/// finalizing or freeing anything, and reports how many objects it
+/// found reachable.
+///
+/// This traces objects the same way a collection does, so it can be
+/// used to check `Trace` implementations. Objects queued for deferred
+/// finalization count as reachable, as they do in a collection.
+///
+/// # Examples
+///
+/// ```
+/// use gc::Gc;
+///
+/// gc::force_collect();
+/// let outer = Gc::new(vec![Gc::new(1), Gc::new(2)]);
+/// assert_eq!(gc::debug::mark_dry_run().reachable, 3);
+///
+/// drop(outer);
+/// let report = gc::debug::mark_dry_run();
+/// assert_eq!((report.reachable, report.unreachable), (0, 3));
+/// ```
+#[must_use]
+pub fn mark_dry_run() -> MarkReport {
+    let (reachable, unreachable) = gc::mark_only();
+    MarkReport {
+        reachable,
+        unreachable,
+    }
+}
+
+/// Returns the groups of objects on the current thread's heap that
+/// refer to each other in a cycle and are only kept alive by references
+/// from other objects.
+///
+/// Each group is a strongly connected set of objects, given by the
+/// addresses of their values as returned by
+/// [`Gc::as_ptr`](crate::Gc::as_ptr). An object that refers to itself
+/// forms a group of its own. Groups containing an object that is
+/// directly rooted, i.e. has a `Gc` pointing to it from outside the
+/// heap, are left out. The others would leak if the same objects were
+/// managed by `Rc`, though they are collected normally by this crate,
+/// so this can help when porting or when looking for unexpected
+/// references.
+///
+/// Unreachable cycles that have not been collected yet are included.
+/// Objects currently borrowed through
+/// [`Gc::get_mut`](crate::Gc::get_mut) are treated as referring to
+/// nothing.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Finalize, Gc, GcCell, Trace};
+///
+/// #[derive(Trace, Finalize)]
+/// struct Node {
+///     next: GcCell<Option<Gc<Node>>>,
+/// }
+///
+/// gc::force_collect();
+/// let node = Gc::new(Node { next: GcCell::new(None) });
+/// *node.next.borrow_mut() = Some(node.clone());
+/// let ptr = Gc::as_ptr(&node).cast::<()>();
+/// assert!(gc::debug::find_cycles().is_empty());
+///
+/// let holder = Gc::new(vec![node]);
+/// assert_eq!(gc::debug::find_cycles(), vec![vec![ptr]]);
+/// ```
+#[must_use]
+pub fn find_cycles() -> Vec<Vec<*const ()>> {
+    let graph = gc::heap_graph();
+    strongly_connected(&graph.edges)
+        .into_iter()
+        .filter(|group| group.len() > 1 || graph.edges[group[0]].contains(&group[0]))
+        .filter(|group| group.iter().all(|&i| graph.nodes[i].1 == 0))
+        .map(|group| group.into_iter().map(|i| graph.nodes[i].0).collect())
+        .collect()
+}
+
+/// Returns a chain of references that keeps `target` alive, or `None`
+/// if it is unreachable and will be freed by the next collection.
+///
+/// `target` is the address of an object's value, as returned by
+/// [`Gc::as_ptr`](crate::Gc::as_ptr). The chain starts at an object
+/// that is directly rooted, i.e. has a `Gc` pointing to it from outside
+/// the heap, and ends at `target`, with each object referring to the
+/// next. It is one of the shortest such chains. `None` is also returned
+/// if `target` is not an object on the current thread's heap.
+///
+/// # Examples
+///
+/// ```
+/// use gc::Gc;
+///
+/// let inner = Gc::new(1);
+/// let outer = Gc::new(vec![inner.clone()]);
+/// let target = Gc::as_ptr(&inner).cast::<()>();
+/// assert_eq!(gc::debug::roots_to(target), Some(vec![target]));
+///
+/// drop(inner);
+/// let path = vec![Gc::as_ptr(&outer).cast::<()>(), target];
+/// assert_eq!(gc::debug::roots_to(target), Some(path));
+///
+/// drop(outer);
+/// assert_eq!(gc::debug::roots_to(target), None);
+/// ```
+#[must_use]
+pub fn roots_to(target: *const ()) -> Option<Vec<*const ()>> {
+    let graph = gc::heap_graph();
+    let target = graph.nodes.iter().position(|&(ptr, _)| ptr == target)?;
+
+    // A breadth-first search from all rooted objects at once, recording
+    // the object each one was first reached from.
+    let mut reached_from = vec![None; graph.nodes.len()];
+    let mut queue: VecDeque<usize> = (0..graph.nodes.len())
+        .filter(|&i| graph.nodes[i].1 > 0)
+        .collect();
+    for &root in &queue {
+        reached_from[root] = Some(root);
+    }
+    while let Some(v) = queue.pop_front() {
+        if v == target {
+            let mut path = vec![graph.nodes[v].0];
+            let mut v = v;
+            while let Some(prev) = reached_from[v].filter(|&prev| prev != v) {
+                path.push(graph.nodes[prev].0);
+                v = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &w in &graph.edges[v] {
+            if reached_from[w].is_none() {
+                reached_from[w] = Some(v);
+                queue.push_back(w);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the addresses of the values of all objects on the current
+/// thread's heap that are directly rooted, i.e. have a `Gc` pointing to
+/// them from outside the heap.
+///
+/// These are the objects a collection starts marking from, apart from
+/// those waiting for a deferred finalizer. Addresses are as returned by
+/// [`Gc::as_ptr`](crate::Gc::as_ptr).
+///
+/// # Examples
+///
+/// ```
+/// use gc::Gc;
+///
+/// let inner = Gc::new(1);
+/// let outer = Gc::new(vec![inner.clone()]);
+/// let roots = gc::debug::root_set();
+/// assert!(roots.contains(&Gc::as_ptr(&inner).cast::<()>()));
+///
+/// drop(inner);
+/// let roots = gc::debug::root_set();
+/// assert!(!roots.contains(&Gc::as_ptr(&outer[0]).cast::<()>()));
+/// ```
+#[must_use]
+pub fn root_set() -> Vec<*const ()> {
+    let mut roots = Vec::new();
+    gc::for_each_box(|gcbox, _| {
+        if gcbox.header().roots() > 0 {
+            roots.push(GcBox::value_ptr(gcbox).cast::<()>());
+        }
+    });
+    roots
+}
+
+/// Splits a graph into its strongly connected components using an
+/// iterative version of Tarjan's algorithm.
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; edges.len()];
+    let mut lowlink = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for start in 0..edges.len() {
+        if index[start] != UNVISITED {
+            continue;
+        }
+        index[start] = next_index;
+        lowlink[start] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start] = true;
+
+        // Each entry is a node and the position of the next edge to follow.
+        let mut work = vec![(start, 0)];
+        while let Some((v, i)) = work.pop() {
+            if let Some(&w) = edges[v].get(i) {
+                work.push((v, i + 1));
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    work.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            if let Some(&(parent, _)) = work.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+            if lowlink[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
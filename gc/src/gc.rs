@@ -1,9 +1,18 @@
 use crate::set_data_ptr;
 use crate::trace::Trace;
-use std::alloc::{Layout, alloc, dealloc};
+use std::alloc::{Layout, alloc, dealloc, handle_alloc_error};
 use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
 use std::mem;
 use std::ptr::{self, NonNull};
+use std::rc::Rc;
+#[cfg(feature = "unstable-global-stats")]
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "unstable-global-stats")]
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "nightly")]
 use std::marker::Unsize;
@@ -12,15 +21,52 @@ struct GcState {
     stats: GcStats,
     config: GcConfig,
     boxes_start: Option<NonNull<GcBox<dyn Trace>>>,
+    // Whether allocations may trigger a collection.
+    auto_collect: bool,
+    // When the last collection finished, for `min_collect_interval`.
+    last_collection: Option<Instant>,
+    // Boxes allocated since the last collection, for
+    // `collect_every_n_allocations`.
+    allocations_since_collection: usize,
+    // A chain of unreachable boxes that have been finalized and unlinked
+    // from `boxes_start`, but not deallocated yet because of
+    // `sweep_budget`.
+    dead_boxes: Option<NonNull<GcBox<dyn Trace>>>,
+    // Unreachable boxes whose finalizers are waiting to run, because of
+    // `defer_finalizers`. They are treated as roots until then.
+    finalize_queue: VecDeque<NonNull<GcBox<dyn Trace>>>,
+    // Whether `run_deferred_finalizers` is running a finalizer.
+    finalizing: bool,
+    // Whether the thread is exiting and this state is being dropped.
+    exiting: bool,
+    // The `Trace::heap_size` each box reported when it was allocated,
+    // by address, for boxes that reported more than zero bytes.
+    heap_sizes: HashMap<*const u8, usize>,
+    // The thread this state belongs to, for `global_stats`.
+    #[cfg(feature = "unstable-global-stats")]
+    thread: ThreadId,
 }
 
 impl Drop for GcState {
     fn drop(&mut self) {
         if !self.config.leak_on_drop {
-            collect_garbage(self);
+            // Deferred finalizers can't run once the thread is exiting, so
+            // run all of them during the final collection.
+            for node in self.finalize_queue.drain(..) {
+                unsafe { node.as_ref().header.clear_finalized() };
+            }
+            self.config.defer_finalizers = false;
+            if self.config.skip_finalizers_on_drop {
+                self.config.run_finalizers = false;
+            }
+            self.exiting = true;
+            collect_garbage(self, None);
         }
         // We have no choice but to leak any remaining nodes that
         // might be referenced from other thread-local variables.
+
+        #[cfg(feature = "unstable-global-stats")]
+        global_stats_lock().remove(&self.thread);
     }
 }
 
@@ -44,20 +90,100 @@ pub fn finalizer_safe() -> bool {
     GC_DROPPING.with(|dropping| !dropping.get())
 }
 
+// The type of the value the sweep phase is dropping, so that a `Drop`
+// impl that dereferences a `Gc` can be named in the resulting panic.
+#[cfg(debug_assertions)]
+thread_local!(static DROPPING_TYPE: Cell<Option<&'static str>> = const { Cell::new(None) });
+
+/// Returns the type name of the value currently being dropped by the
+/// sweep phase, if known.
+pub(crate) fn dropping_type() -> Option<&'static str> {
+    #[cfg(debug_assertions)]
+    return DROPPING_TYPE.try_with(Cell::get).ok().flatten();
+    #[cfg(not(debug_assertions))]
+    None
+}
+
+/// Information about the collection that is running a finalizer, as
+/// passed to `Finalize::finalize_with` and returned by
+/// [`finalize_context`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FinalizeContext {
+    /// `true` if this is the final collection, run when the thread exits.
+    pub thread_exit: bool,
+    /// `true` if the finalizer was deferred until after the collection,
+    /// because `defer_finalizers` is set.
+    pub deferred: bool,
+}
+
+thread_local!(static FINALIZE_CONTEXT: Cell<Option<FinalizeContext>> = const { Cell::new(None) });
+
+/// Returns information about the collection that is running the
+/// current finalizer, or `None` if no finalizer is being run by the
+/// collector.
+///
+/// `Finalize::finalize` is also called when a value is dropped outside
+/// of a collection, in which case this returns `None`.
+#[must_use]
+pub fn finalize_context() -> Option<FinalizeContext> {
+    FINALIZE_CONTEXT.get()
+}
+
+// Sets the value returned by `finalize_context` until it is dropped.
+struct FinalizeContextGuard(Option<FinalizeContext>);
+impl FinalizeContextGuard {
+    fn new(context: FinalizeContext) -> FinalizeContextGuard {
+        FinalizeContextGuard(FINALIZE_CONTEXT.replace(Some(context)))
+    }
+}
+impl Drop for FinalizeContextGuard {
+    fn drop(&mut self) {
+        FINALIZE_CONTEXT.set(self.0);
+    }
+}
+
 // The garbage collector's internal state.
 thread_local!(static GC_STATE: RefCell<GcState> = RefCell::new(GcState {
     stats: GcStats::default(),
     config: GcConfig::default(),
     boxes_start: None,
+    auto_collect: true,
+    last_collection: None,
+    allocations_since_collection: 0,
+    dead_boxes: None,
+    finalize_queue: VecDeque::new(),
+    finalizing: false,
+    exiting: false,
+    heap_sizes: HashMap::new(),
+    #[cfg(feature = "unstable-global-stats")]
+    thread: std::thread::current().id(),
 }));
 
+// The maximum nesting of `GcBox::trace_inner` calls. Boxes found
+// deeper than this are put on `MARK_STACK` instead of being traced
+// recursively, so marking long chains of `Gc`s can't overflow the stack.
+const MAX_MARK_DEPTH: usize = 256;
+
+thread_local! {
+    static MARK_DEPTH: Cell<usize> = const { Cell::new(0) };
+    // Headers of boxes that have been marked but whose data has not been
+    // traced yet.
+    static MARK_STACK: RefCell<Vec<NonNull<GcBoxHeader>>> = const { RefCell::new(Vec::new()) };
+}
+
 const MARK_MASK: usize = 1 << (usize::BITS - 1);
-const ROOTS_MASK: usize = !MARK_MASK;
+// Set once the box has been finalized, or queued for finalization.
+const FINALIZED_MASK: usize = 1 << (usize::BITS - 2);
+// Set while the data is mutably borrowed through `Gc::get_mut`.
+const BORROWED_MASK: usize = 1 << (usize::BITS - 3);
+const ROOTS_MASK: usize = !(MARK_MASK | FINALIZED_MASK | BORROWED_MASK);
 const ROOTS_MAX: usize = ROOTS_MASK; // max allowed value of roots
 
 pub(crate) struct GcBoxHeader {
-    roots: Cell<usize>, // high bit is used as mark flag
+    roots: Cell<usize>, // the three high bits are used as flags
     next: Cell<Option<NonNull<GcBox<dyn Trace>>>>,
+    tag: Cell<u8>, // for the user, see `Gc::tag`
 }
 
 impl GcBoxHeader {
@@ -66,6 +192,7 @@ impl GcBoxHeader {
         GcBoxHeader {
             roots: Cell::new(1), // unmarked and roots count = 1
             next: Cell::new(None),
+            tag: Cell::new(0),
         }
     }
 
@@ -76,14 +203,23 @@ impl GcBoxHeader {
 
     #[inline]
     pub fn inc_roots(&self) {
-        let roots = self.roots.get();
-
         // abort if the count overflows to prevent `mem::forget` loops
         // that could otherwise lead to erroneous drops
+        if !self.try_inc_roots() {
+            panic!("roots counter overflow");
+        }
+    }
+
+    /// Increments the root count, unless that would overflow it, in which
+    /// case it returns `false`.
+    #[inline]
+    pub fn try_inc_roots(&self) -> bool {
+        let roots = self.roots.get();
         if (roots & ROOTS_MASK) < ROOTS_MAX {
-            self.roots.set(roots + 1); // we checked that this wont affect the high bit
+            self.roots.set(roots + 1); // we checked that this wont affect the high bits
+            true
         } else {
-            panic!("roots counter overflow");
+            false
         }
     }
 
@@ -92,6 +228,16 @@ impl GcBoxHeader {
         self.roots.set(self.roots.get() - 1); // no underflow check
     }
 
+    #[inline]
+    pub fn tag(&self) -> u8 {
+        self.tag.get()
+    }
+
+    #[inline]
+    pub fn set_tag(&self, tag: u8) {
+        self.tag.set(tag);
+    }
+
     #[inline]
     pub fn is_marked(&self) -> bool {
         self.roots.get() & MARK_MASK != 0
@@ -106,6 +252,36 @@ impl GcBoxHeader {
     pub fn unmark(&self) {
         self.roots.set(self.roots.get() & !MARK_MASK);
     }
+
+    #[inline]
+    pub fn is_finalized(&self) -> bool {
+        self.roots.get() & FINALIZED_MASK != 0
+    }
+
+    #[inline]
+    pub fn set_finalized(&self) {
+        self.roots.set(self.roots.get() | FINALIZED_MASK);
+    }
+
+    #[inline]
+    pub fn clear_finalized(&self) {
+        self.roots.set(self.roots.get() & !FINALIZED_MASK);
+    }
+
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        self.roots.get() & BORROWED_MASK != 0
+    }
+
+    #[inline]
+    pub fn set_borrowed(&self, borrowed: bool) {
+        let roots = self.roots.get() & !BORROWED_MASK;
+        self.roots.set(if borrowed {
+            roots | BORROWED_MASK
+        } else {
+            roots
+        });
+    }
 }
 
 #[repr(C)] // to justify the layout computations in GcBox::from_box, Gc::from_raw
@@ -121,15 +297,84 @@ impl<T: Trace> GcBox<T> {
     ///
     /// A `GcBox` allocated this way starts its life rooted.
     pub(crate) fn new(value: T) -> NonNull<Self> {
-        let gcbox = NonNull::from(Box::leak(Box::new(GcBox {
-            header: GcBoxHeader::new(),
-            data: value,
-        })));
-        unsafe { insert_gcbox(gcbox) };
-        gcbox
+        match Self::try_new(value) {
+            Ok(gcbox) => gcbox,
+            Err(failure) => failure.raise(Layout::new::<Self>()),
+        }
+    }
+
+    /// Like `new`, but returns an error, dropping `value`, if the memory
+    /// can't be allocated even after collecting garbage, or if it would
+    /// exceed `GcConfig::memory_limit`.
+    pub(crate) fn try_new(value: T) -> Result<NonNull<Self>, AllocFailure> {
+        // Allocate the GcBox in a way that's compatible with Box, since
+        // the collector will deallocate it via Box::from_raw.
+        let gcbox = alloc_gcbox(Layout::new::<Self>())
+            .ok_or(AllocFailure::OutOfMemory)?
+            .cast::<Self>();
+        unsafe {
+            gcbox.as_ptr().write(GcBox {
+                header: GcBoxHeader::new(),
+                data: value,
+            });
+            insert_gcbox(gcbox)?;
+        }
+        Ok(gcbox)
+    }
+}
+
+/// Why a `GcBox` couldn't be allocated.
+pub(crate) enum AllocFailure {
+    /// The allocator returned null, even after collecting garbage.
+    OutOfMemory,
+    /// Allocating `bytes` would have exceeded the memory limit, with
+    /// `allocated` bytes still in use after collecting garbage.
+    MemoryLimit {
+        bytes: usize,
+        allocated: usize,
+        limit: usize,
+    },
+}
+
+impl AllocFailure {
+    /// Reports the failure the way infallible allocations do.
+    fn raise(self, layout: Layout) -> ! {
+        match self {
+            AllocFailure::OutOfMemory => handle_alloc_error(layout),
+            AllocFailure::MemoryLimit {
+                bytes,
+                allocated,
+                limit,
+            } => panic!(
+                "allocating {bytes} bytes would exceed the garbage collector's memory limit \
+                 of {limit} bytes ({allocated} bytes are still in use after collecting)"
+            ),
+        }
     }
 }
 
+/// Allocates memory for a `GcBox`. If that fails, garbage is collected
+/// and the allocation is tried once more.
+///
+/// # Panics
+///
+/// Panics if the collector is running, since the new box couldn't be
+/// added to the chain.
+fn alloc_gcbox(layout: Layout) -> Option<NonNull<u8>> {
+    if GC_STATE.with(|st| st.try_borrow_mut().is_err()) {
+        panic!(
+            "a Gc can't be allocated while the garbage collector is running, \
+             e.g. from a Trace impl, a Drop impl or a finalizer \
+             (finalizers may allocate if GcConfig::defer_finalizers is set)"
+        );
+    }
+    if let Some(ptr) = NonNull::new(unsafe { alloc(layout) }) {
+        return Some(ptr);
+    }
+    force_collect();
+    NonNull::new(unsafe { alloc(layout) })
+}
+
 impl<
     #[cfg(not(feature = "nightly"))] T: Trace,
     #[cfg(feature = "nightly")] T: Trace + Unsize<dyn Trace> + ?Sized,
@@ -146,12 +391,15 @@ impl<
         // This relies on GcBox being #[repr(C)].
         let gcbox_layout = header_layout.extend(value_layout).unwrap().0.pad_to_align();
 
-        unsafe {
-            // Allocate the GcBox in a way that's compatible with Box,
-            // since the collector will deallocate it via
-            // Box::from_raw.
-            let gcbox_addr = alloc(gcbox_layout);
+        // Allocate the GcBox in a way that's compatible with Box,
+        // since the collector will deallocate it via
+        // Box::from_raw.
+        let Some(gcbox_addr) = alloc_gcbox(gcbox_layout) else {
+            handle_alloc_error(gcbox_layout);
+        };
+        let gcbox_addr = gcbox_addr.as_ptr();
 
+        unsafe {
             // Since we're not allowed to move the value out of an
             // active Box, and we will need to deallocate the Box
             // without calling the destructor, convert it to a raw
@@ -176,27 +424,79 @@ impl<
 
             // Add the new GcBox to the chain and return it.
             let gcbox = NonNull::new_unchecked(gcbox);
-            insert_gcbox(gcbox);
+            if let Err(failure) = insert_gcbox(gcbox) {
+                failure.raise(gcbox_layout);
+            }
             gcbox
         }
     }
 }
 
+impl GcState {
+    /// Returns the memory limit, if allocating `bytes` more would exceed
+    /// it.
+    fn exceeded_limit(&self, bytes: usize) -> Option<usize> {
+        self.config
+            .memory_limit
+            .filter(|&limit| self.stats.bytes_allocated + bytes > limit)
+    }
+
+    /// Returns `true` if `min_collect_interval` has not passed since the
+    /// previous collection.
+    fn throttled(&self) -> bool {
+        match (self.config.min_collect_interval, self.last_collection) {
+            (Some(interval), Some(last)) => last.elapsed() < interval,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if allocating `bytes` more should trigger a
+    /// collection first.
+    fn should_collect(&self, bytes: usize) -> bool {
+        // XXX We should probably be more clever about collecting
+        let throttled = self.throttled();
+        // Going over the memory limit forces a full collection, whatever
+        // the threshold and interval say.
+        let triggered = self.stats.bytes_allocated > self.config.threshold
+            || self
+                .config
+                .collect_every_n_allocations
+                .is_some_and(|n| self.allocations_since_collection >= n);
+        self.auto_collect && (self.exceeded_limit(bytes).is_some() || !throttled && triggered)
+    }
+}
+
 /// Add a new `GcBox` to the current thread's `GcBox` chain. This
 /// might trigger a collection first if enough bytes have been
 /// allocated since the previous collection.
 ///
+/// If the box would exceed the memory limit, it is freed instead and
+/// an error is returned.
+///
 /// # Safety
 ///
 /// `gcbox` must point to a valid `GcBox` that is not yet in a `GcBox`
 /// chain.
-unsafe fn insert_gcbox(gcbox: NonNull<GcBox<dyn Trace>>) {
-    GC_STATE.with(|st| {
-        let mut st = st.borrow_mut();
+unsafe fn insert_gcbox(gcbox: NonNull<GcBox<dyn Trace>>) -> Result<(), AllocFailure> {
+    let heap_size = unsafe { gcbox.as_ref().data.heap_size() };
+    let bytes = mem::size_of_val::<GcBox<_>>(unsafe { gcbox.as_ref() }) + heap_size;
+    // Decided once, so that a collection always follows the
+    // `on_pre_collect` hook, whatever the hook changes.
+    let collect = GC_STATE.with(|st| st.borrow().should_collect(bytes));
+    if collect {
+        run_pre_collect();
+    }
 
-        // XXX We should probably be more clever about collecting
-        if st.stats.bytes_allocated > st.config.threshold {
-            collect_garbage(&mut st);
+    let inserted = GC_STATE.with(|st| {
+        let mut st = st.borrow_mut();
+        let pressured = st.exceeded_limit(bytes).is_some();
+        if collect {
+            let budget = if pressured {
+                None
+            } else {
+                st.config.sweep_budget
+            };
+            collect_garbage(&mut st, budget);
 
             if st.stats.bytes_allocated as f64
                 > st.config.threshold as f64 * st.config.used_space_ratio
@@ -207,14 +507,49 @@ unsafe fn insert_gcbox(gcbox: NonNull<GcBox<dyn Trace>>) {
                 st.config.threshold =
                     (st.stats.bytes_allocated as f64 / st.config.used_space_ratio) as usize;
             }
+        } else if st.dead_boxes.is_some() {
+            let budget = st.config.sweep_budget;
+            unsafe { drop_dead_boxes(&mut st.dead_boxes, budget) };
+        }
+
+        if let Some(limit) = st.exceeded_limit(bytes) {
+            return Err(AllocFailure::MemoryLimit {
+                bytes,
+                allocated: st.stats.bytes_allocated,
+                limit,
+            });
         }
 
         let next = st.boxes_start.replace(gcbox);
         unsafe { gcbox.as_ref().header.next.set(next) };
+        if heap_size > 0 {
+            st.heap_sizes.insert(gcbox.as_ptr().cast(), heap_size);
+        }
 
         // We allocated some bytes! Let's record it
-        st.stats.bytes_allocated += mem::size_of_val::<GcBox<_>>(unsafe { gcbox.as_ref() });
+        st.stats.bytes_allocated += bytes;
+        st.stats.total_bytes_allocated += bytes;
+        st.stats.peak_bytes_allocated = st.stats.peak_bytes_allocated.max(st.stats.bytes_allocated);
+        st.stats.live_objects += 1;
+        st.allocations_since_collection += 1;
+
+        Ok(collect)
     });
+
+    match inserted {
+        Ok(collected) => {
+            if collected {
+                run_deferred_finalizers();
+                run_on_collect();
+            }
+            Ok(())
+        }
+        Err(failure) => {
+            // The box was never added to the chain, so it is ours to free.
+            drop(unsafe { Box::from_raw(gcbox.as_ptr()) });
+            Err(failure)
+        }
+    }
 }
 
 impl<T: ?Sized> GcBox<T> {
@@ -231,11 +566,59 @@ impl<T: Trace + ?Sized> GcBox<T> {
     pub(crate) unsafe fn trace_inner(&self) {
         if !self.header.is_marked() {
             self.header.mark();
-            unsafe { self.data.trace() };
+            let depth = MARK_DEPTH.get();
+            // MARK_STACK is unavailable while the thread's locals are being
+            // destroyed, in which case we keep recursing.
+            let deferred = depth >= MAX_MARK_DEPTH
+                && MARK_STACK
+                    .try_with(|stack| stack.borrow_mut().push(NonNull::from(&self.header)))
+                    .is_ok();
+            // A box borrowed by `Gc::get_mut` has its contents rooted, and
+            // its data must not be read while the borrow lasts.
+            if !deferred && !self.header.is_borrowed() {
+                MARK_DEPTH.set(depth + 1);
+                unsafe { self.data.trace() };
+                MARK_DEPTH.set(depth);
+            }
         }
     }
 }
 
+impl GcBox<dyn Trace> {
+    /// Returns the headers of the boxes this box's data refers to
+    /// directly. No box may be marked, and all are left unmarked. The
+    /// data must not be borrowed by `Gc::get_mut`.
+    ///
+    /// Returns nothing if the thread's locals are being destroyed.
+    #[cfg(any(feature = "unstable-config", feature = "unstable-debug"))]
+    fn traced_edges(&self) -> Vec<NonNull<GcBoxHeader>> {
+        // Restores the mark state even if a `trace` implementation panics.
+        struct Restore(usize);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                for header in MARK_STACK.with_borrow_mut(mem::take) {
+                    unsafe { header.as_ref().unmark() };
+                }
+                MARK_DEPTH.set(self.0);
+            }
+        }
+
+        if MARK_STACK.try_with(|_| ()).is_err() {
+            return Vec::new();
+        }
+        // At the maximum depth, `trace_inner` marks each box it is called
+        // on and pushes it on `MARK_STACK` without tracing it, so the
+        // stack ends up holding exactly the boxes the data refers to.
+        let _restore = Restore(MARK_DEPTH.replace(MAX_MARK_DEPTH));
+        unsafe { self.data.trace() };
+        let edges = MARK_STACK.with_borrow_mut(mem::take);
+        for header in &edges {
+            unsafe { header.as_ref().unmark() };
+        }
+        edges
+    }
+}
+
 impl<T: ?Sized> GcBox<T> {
     /// Increases the root count on this `GcBox`.
     /// Roots prevent the `GcBox` from being destroyed by the garbage collector.
@@ -243,6 +626,12 @@ impl<T: ?Sized> GcBox<T> {
         self.header.inc_roots();
     }
 
+    /// Like `root_inner`, but returns `false` instead of panicking if the
+    /// root count would overflow.
+    pub(crate) unsafe fn try_root_inner(&self) -> bool {
+        self.header.try_inc_roots()
+    }
+
     /// Decreases the root count on this `GcBox`.
     /// Roots prevent the `GcBox` from being destroyed by the garbage collector.
     pub(crate) unsafe fn unroot_inner(&self) {
@@ -258,85 +647,878 @@ impl<T: ?Sized> GcBox<T> {
     pub(crate) fn value(&self) -> &T {
         &self.data
     }
-}
 
-/// Collects garbage.
-fn collect_garbage(st: &mut GcState) {
-    struct Unmarked<'a> {
-        incoming: &'a Cell<Option<NonNull<GcBox<dyn Trace>>>>,
-        this: NonNull<GcBox<dyn Trace>>,
+    /// Returns a reference to the `GcBox`'s header.
+    pub(crate) fn header(&self) -> &GcBoxHeader {
+        &self.header
     }
-    unsafe fn mark(head: &Cell<Option<NonNull<GcBox<dyn Trace>>>>) -> Vec<Unmarked<'_>> {
-        // Walk the tree, tracing and marking the nodes
-        let mut mark_head = head.get();
-        while let Some(node) = mark_head {
+
+    /// Returns `true` if this `GcBox` has exactly one root, no `GcBox`
+    /// on the current thread's chain refers to it, and the collector
+    /// isn't done with it: it is neither waiting for a deferred
+    /// finalizer nor waiting to be deallocated.
+    ///
+    /// This traces the data of every `GcBox` on the chain, so it costs
+    /// about as much as the mark phase of a collection.
+    pub(crate) fn is_unique(&self) -> bool {
+        if self.header.roots() != 1 {
+            return false;
+        }
+
+        GC_STATE.with(|st| {
+            let st = st.borrow_mut();
+            let is_self = |node: &NonNull<GcBox<dyn Trace>>| unsafe {
+                ptr::eq(&node.as_ref().header, &self.header)
+            };
+            // A queued finalizer still uses the box, and a dead box is
+            // freed by the collector.
+            if st.finalize_queue.iter().any(is_self) {
+                return false;
+            }
+            let mut dead = st.dead_boxes;
+            while let Some(node) = dead {
+                if is_self(&node) {
+                    return false;
+                }
+                dead = unsafe { node.as_ref().header.next.get() };
+            }
+
             unsafe {
-                if node.as_ref().header.roots() > 0 {
-                    node.as_ref().trace_inner();
+                // Mark everything referenced by any box. Boxes which are
+                // already marked have had their data traced by trace_inner.
+                start_marking();
+                let mut head = st.boxes_start;
+                while let Some(node) = head {
+                    let header = &node.as_ref().header;
+                    if !header.is_marked() && !header.is_borrowed() {
+                        node.as_ref().data.trace();
+                    }
+                    head = node.as_ref().header.next.get();
                 }
-                mark_head = node.as_ref().header.next.get();
+                finish_marking(st.boxes_start, None);
+                let referenced = self.header.is_marked();
+
+                let mut head = st.boxes_start;
+                while let Some(node) = head {
+                    node.as_ref().header.unmark();
+                    head = node.as_ref().header.next.get();
+                }
+                !referenced
             }
+        })
+    }
+}
+
+impl<T: Trace> GcBox<T> {
+    /// Unlinks this `GcBox` from the current thread's chain, deallocates
+    /// it and returns the value it contained.
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to a valid `GcBox` on the thread-local `GcBox`
+    /// chain, and no `Gc` may use it afterwards.
+    pub(crate) unsafe fn take(this: NonNull<Self>) -> T {
+        GC_STATE.with(|st| {
+            let mut st = st.borrow_mut();
+            unsafe {
+                let mut incoming = Cell::from_mut(&mut st.boxes_start);
+                while let Some(node) = incoming.get() {
+                    if ptr::addr_eq(node.as_ptr(), this.as_ptr()) {
+                        incoming.set(node.as_ref().header.next.take());
+                        break;
+                    }
+                    incoming = &node.as_ref().header.next;
+                }
+                let heap_size = st.heap_sizes.remove(&this.as_ptr().cast_const().cast());
+                st.stats.bytes_allocated -= mem::size_of::<GcBox<T>>() + heap_size.unwrap_or(0);
+                st.stats.live_objects -= 1;
+
+                let value = ptr::addr_of!((*this.as_ptr()).data).read();
+                dealloc(this.as_ptr().cast::<u8>(), Layout::new::<GcBox<T>>());
+                value
+            }
+        })
+    }
+}
+
+/// Resets the state used by `GcBox::trace_inner`, which may have been
+/// left behind if a previous mark phase panicked.
+fn start_marking() {
+    MARK_DEPTH.set(0);
+    let _ = MARK_STACK.try_with(|stack| stack.borrow_mut().clear());
+}
+
+/// Traces the data of the boxes that `GcBox::trace_inner` deferred to
+/// `MARK_STACK`, until no boxes are left to trace. Returns `false`,
+/// leaving the rest of the stack, if `deadline` passes first.
+///
+/// # Safety
+///
+/// `head` must be the start of the current thread's `GcBox` chain.
+unsafe fn finish_marking(
+    head: Option<NonNull<GcBox<dyn Trace>>>,
+    deadline: Option<Instant>,
+) -> bool {
+    if MARK_STACK
+        .try_with(|stack| stack.borrow().is_empty())
+        .unwrap_or(true)
+    {
+        return true;
+    }
+
+    // The stack only holds headers, so look up the type of each box from
+    // the chain.
+    let mut boxes = HashMap::new();
+    let mut node = head;
+    while let Some(gcbox) = node {
+        unsafe {
+            boxes.insert(NonNull::from(&gcbox.as_ref().header), gcbox);
+            node = gcbox.as_ref().header.next.get();
+        }
+    }
+
+    while let Some(header) = MARK_STACK.with_borrow_mut(Vec::pop) {
+        if past(deadline) {
+            return false;
+        }
+        if unsafe { !header.as_ref().is_borrowed() } {
+            unsafe { boxes[&header].as_ref().data.trace() };
+        }
+    }
+    true
+}
+
+fn past(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Calls `f` with every `GcBox` on the current thread's chain, and the
+/// `Trace::heap_size` it reported when it was allocated. The data of
+/// boxes mutably borrowed through `Gc::get_mut` must not be read.
+/// The collector stays borrowed until `f` returns, so `f` can't
+/// allocate or collect.
+#[cfg(feature = "unstable-debug")]
+pub(crate) fn for_each_box(mut f: impl FnMut(&GcBox<dyn Trace>, usize)) {
+    GC_STATE.with(|st| {
+        let st = st.borrow();
+        let mut head = st.boxes_start;
+        while let Some(node) = head {
+            let heap_size = st.heap_sizes.get(&node.as_ptr().cast_const().cast());
+            let node = unsafe { node.as_ref() };
+            f(node, heap_size.copied().unwrap_or(0));
+            head = node.header.next.get();
         }
+    });
+}
 
-        // Collect a vector of all of the nodes which were not marked,
-        // and unmark the ones which were.
-        let mut unmarked = Vec::new();
-        let mut unmark_head = head;
-        while let Some(node) = unmark_head.get() {
+/// Runs the mark phase of a collection without finalizing or sweeping,
+/// and returns the number of reachable and unreachable boxes. All boxes
+/// are left unmarked.
+#[cfg(feature = "unstable-debug")]
+pub(crate) fn mark_only() -> (usize, usize) {
+    GC_STATE.with(|st| {
+        let mut st = st.borrow_mut();
+        let st = &mut *st;
+        let mut total = 0;
+        let mut head = st.boxes_start;
+        while let Some(node) = head {
+            total += 1;
+            head = unsafe { node.as_ref().header.next.get() };
+        }
+        let unreachable =
+            unsafe { mark(Cell::from_mut(&mut st.boxes_start), &st.finalize_queue) }.len();
+        (total - unreachable, unreachable)
+    })
+}
+
+/// A snapshot of the boxes on the current thread's heap and the
+/// references between them.
+#[cfg(feature = "unstable-debug")]
+pub(crate) struct HeapGraph {
+    /// The address of each box's value and its root count.
+    pub(crate) nodes: Vec<(*const (), usize)>,
+    /// The indices of the boxes each box refers to directly.
+    pub(crate) edges: Vec<Vec<usize>>,
+}
+
+/// Builds a [`HeapGraph`] by tracing the data of every box on its own.
+/// Boxes mutably borrowed through `Gc::get_mut` have no outgoing edges,
+/// since their data can't be read.
+#[cfg(feature = "unstable-debug")]
+pub(crate) fn heap_graph() -> HeapGraph {
+    GC_STATE.with(|st| {
+        let st = st.borrow();
+        let mut index = HashMap::new();
+        let mut boxes = Vec::new();
+        let mut nodes = Vec::new();
+        let mut head = st.boxes_start;
+        while let Some(node) = head {
+            let header = unsafe { &node.as_ref().header };
+            index.insert(NonNull::from(header), boxes.len());
+            boxes.push(node);
+            nodes.push((GcBox::value_ptr(node.as_ptr()).cast::<()>(), header.roots()));
+            head = header.next.get();
+        }
+
+        let edges = boxes
+            .iter()
+            .map(|node| {
+                let node = unsafe { node.as_ref() };
+                if node.header.is_borrowed() {
+                    return Vec::new();
+                }
+                node.traced_edges()
+                    .iter()
+                    .filter_map(|header| index.get(header).copied())
+                    .collect()
+            })
+            .collect();
+        HeapGraph { nodes, edges }
+    })
+}
+
+/// A summary of the work done by a single garbage collection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollectionReport {
+    /// The number of objects that were reclaimed. With a `sweep_budget`,
+    /// some of them may only be deallocated later.
+    pub objects_freed: usize,
+    /// The number of bytes that were reclaimed.
+    pub bytes_freed: usize,
+    /// The number of objects that were found unreachable and finalized,
+    /// or queued to be finalized if `defer_finalizers` is set. This can
+    /// be larger than `objects_freed` if finalizers made some of them
+    /// reachable again.
+    pub objects_finalized: usize,
+}
+
+// An unreachable box found by `mark`, and the link in the chain that
+// points to it.
+struct Unmarked<'a> {
+    incoming: &'a Cell<Option<NonNull<GcBox<dyn Trace>>>>,
+    this: NonNull<GcBox<dyn Trace>>,
+}
+
+// Marks every box reachable from a root or from the finalize queue, and
+// returns the others. All boxes are left unmarked.
+unsafe fn mark<'a>(
+    head: &'a Cell<Option<NonNull<GcBox<dyn Trace>>>>,
+    finalize_queue: &VecDeque<NonNull<GcBox<dyn Trace>>>,
+) -> Vec<Unmarked<'a>> {
+    unsafe { mark_until(head, finalize_queue, None) }
+        .expect("marking without a deadline is never abandoned")
+}
+
+// Like `mark`, but gives up and returns `None` if `deadline` passes
+// before marking has finished. It is checked between roots and between
+// the boxes on `MARK_STACK`.
+unsafe fn mark_until<'a>(
+    head: &'a Cell<Option<NonNull<GcBox<dyn Trace>>>>,
+    finalize_queue: &VecDeque<NonNull<GcBox<dyn Trace>>>,
+    deadline: Option<Instant>,
+) -> Option<Vec<Unmarked<'a>>> {
+    // Walk the tree, tracing and marking the nodes
+    start_marking();
+    let mut finished = true;
+    let mut mark_head = head.get();
+    while let Some(node) = mark_head {
+        if past(deadline) {
+            finished = false;
+            break;
+        }
+        unsafe {
+            if node.as_ref().header.roots() > 0 {
+                node.as_ref().trace_inner();
+            }
+            mark_head = node.as_ref().header.next.get();
+        }
+    }
+    if finished {
+        for node in finalize_queue {
+            unsafe { node.as_ref().trace_inner() };
+        }
+        finished = unsafe { finish_marking(head.get(), deadline) };
+    }
+    if !finished {
+        start_marking();
+        let mut unmark_head = head.get();
+        while let Some(node) = unmark_head {
             unsafe {
-                if node.as_ref().header.is_marked() {
-                    node.as_ref().header.unmark();
-                } else {
-                    unmarked.push(Unmarked {
-                        incoming: unmark_head,
-                        this: node,
-                    });
+                node.as_ref().header.unmark();
+                unmark_head = node.as_ref().header.next.get();
+            }
+        }
+        return None;
+    }
+
+    // Collect a vector of all of the nodes which were not marked,
+    // and unmark the ones which were.
+    let mut unmarked = Vec::new();
+    let mut unmark_head = head;
+    while let Some(node) = unmark_head.get() {
+        unsafe {
+            if node.as_ref().header.is_marked() {
+                node.as_ref().header.unmark();
+            } else {
+                unmarked.push(Unmarked {
+                    incoming: unmark_head,
+                    this: node,
+                });
+            }
+            unmark_head = &node.as_ref().header.next;
+        }
+    }
+    Some(unmarked)
+}
+
+// Orders the unmarked boxes so that each box comes after the boxes it
+// refers to, except within cycles, by a depth-first search.
+#[cfg(feature = "unstable-config")]
+unsafe fn children_first(unmarked: &[Unmarked<'_>]) -> Vec<usize> {
+    let index: HashMap<_, _> = unmarked
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (NonNull::from(unsafe { &node.this.as_ref().header }), i))
+        .collect();
+    let edges: Vec<Vec<usize>> = unmarked
+        .iter()
+        .map(|node| {
+            let edges = unsafe { node.this.as_ref() }.traced_edges();
+            edges
+                .iter()
+                .filter_map(|header| index.get(header).copied())
+                .collect()
+        })
+        .collect();
+
+    let mut visited = vec![false; unmarked.len()];
+    let mut order = Vec::with_capacity(unmarked.len());
+    for start in 0..unmarked.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        // Each entry is a box and the position of the next edge to follow.
+        let mut stack = vec![(start, 0)];
+        while let Some((v, i)) = stack.pop() {
+            if let Some(&w) = edges[v].get(i) {
+                stack.push((v, i + 1));
+                if !visited[w] {
+                    visited[w] = true;
+                    stack.push((w, 0));
                 }
-                unmark_head = &node.as_ref().header.next;
+            } else {
+                order.push(v);
             }
         }
-        unmarked
     }
+    order
+}
 
-    unsafe fn sweep(finalized: Vec<Unmarked<'_>>, bytes_allocated: &mut usize) {
-        let _guard = DropGuard::new();
-        for node in finalized.into_iter().rev() {
-            if unsafe { node.this.as_ref().header.is_marked() } {
-                continue;
+// Finalizes the unmarked boxes that have not been finalized yet, or
+// queues them to be finalized if `defer_finalizers` is set, and returns
+// how many there were. With `mark_finalized`, boxes finalized right away
+// are marked so that they are not finalized again either.
+unsafe fn finalize_unmarked(
+    config: &GcConfig,
+    finalize_queue: &mut VecDeque<NonNull<GcBox<dyn Trace>>>,
+    thread_exit: bool,
+    unmarked: &[Unmarked<'_>],
+    mark_finalized: bool,
+) -> usize {
+    let _context = FinalizeContextGuard::new(FinalizeContext {
+        thread_exit,
+        deferred: false,
+    });
+    let order: Vec<usize> = match config.finalize_order {
+        #[cfg(feature = "unstable-config")]
+        FinalizeOrder::ChildrenFirst if !thread_exit => unsafe { children_first(unmarked) },
+        _ => (0..unmarked.len()).collect(),
+    };
+    let mut finalized = 0;
+    // Boxes finalized by a deferred finalizer are not finalized
+    // again, even if the finalizer made them reachable for a while.
+    for node in order.into_iter().map(|i| &unmarked[i]) {
+        let header = unsafe { &node.this.as_ref().header };
+        if header.is_finalized() {
+            continue;
+        }
+        if config.defer_finalizers {
+            header.set_finalized();
+            finalize_queue.push_back(node.this);
+        } else {
+            if mark_finalized {
+                header.set_finalized();
             }
-            let incoming = node.incoming;
-            let node = unsafe { Box::from_raw(node.this.as_ptr()) };
-            *bytes_allocated -= mem::size_of_val::<GcBox<_>>(&*node);
-            incoming.set(node.header.next.take());
+            Trace::finalize_glue(unsafe { &node.this.as_ref().data });
         }
+        finalized += 1;
     }
+    finalized
+}
 
-    st.stats.collections_performed += 1;
+/// Collects garbage, deallocating at most `budget` unreachable boxes
+/// (or all of them, if `None`). The rest are left on `st.dead_boxes`.
+fn collect_garbage(st: &mut GcState, budget: Option<usize>) -> CollectionReport {
+    collect_garbage_until(st, budget, None)
+        .expect("a collection without a deadline is never abandoned")
+}
 
+/// Like `collect_garbage`, but abandons the collection without freeing
+/// anything and returns `None` if marking doesn't finish by `deadline`.
+fn collect_garbage_until(
+    st: &mut GcState,
+    budget: Option<usize>,
+    deadline: Option<Instant>,
+) -> Option<CollectionReport> {
+    // Moves the unmarked boxes from the chain to `dead`.
+    unsafe fn sweep(
+        finalized: Vec<Unmarked<'_>>,
+        stats: &mut GcStats,
+        heap_sizes: &mut HashMap<*const u8, usize>,
+        dead: &mut Option<NonNull<GcBox<dyn Trace>>>,
+    ) -> usize {
+        let mut freed = 0;
+        for node in finalized.into_iter().rev() {
+            let this = unsafe { node.this.as_ref() };
+            let heap_size = if heap_sizes.is_empty() {
+                None
+            } else {
+                heap_sizes.remove(&node.this.as_ptr().cast_const().cast())
+            };
+            stats.bytes_allocated -= mem::size_of_val::<GcBox<_>>(this) + heap_size.unwrap_or(0);
+            stats.live_objects -= 1;
+            node.incoming
+                .set(this.header.next.replace(dead.replace(node.this)));
+            freed += 1;
+        }
+        freed
+    }
+
+    let start = Instant::now();
+
+    let mut report = CollectionReport::default();
     unsafe {
         let head = Cell::from_mut(&mut st.boxes_start);
-        let unmarked = mark(head);
-        if unmarked.is_empty() {
-            return;
+        let mut unmarked = mark_until(head, &st.finalize_queue, deadline)?;
+        st.stats.collections_performed += 1;
+        if !unmarked.is_empty() && st.config.run_finalizers {
+            report.objects_finalized = finalize_unmarked(
+                &st.config,
+                &mut st.finalize_queue,
+                st.exiting,
+                &unmarked,
+                false,
+            );
+            // Finalizers may have made some of the boxes reachable again.
+            // Boxes that only became unreachable during finalization have
+            // not been finalized, so they are left for the next collection.
+            if report.objects_finalized > 0 {
+                let still_unmarked: HashSet<_> = mark(head, &st.finalize_queue)
+                    .into_iter()
+                    .map(|node| node.this.cast::<()>())
+                    .collect();
+                unmarked.retain(|node| still_unmarked.contains(&node.this.cast::<()>()));
+            }
         }
-        for node in &unmarked {
-            Trace::finalize_glue(&node.this.as_ref().data);
+        let bytes_before = st.stats.bytes_allocated;
+        report.objects_freed = sweep(
+            unmarked,
+            &mut st.stats,
+            &mut st.heap_sizes,
+            &mut st.dead_boxes,
+        );
+        report.bytes_freed = bytes_before - st.stats.bytes_allocated;
+        drop_dead_boxes(&mut st.dead_boxes, budget);
+    }
+
+    let end = Instant::now();
+    st.stats.last_collection_duration = end - start;
+    st.stats.total_collection_time += end - start;
+    st.last_collection = Some(end);
+    st.allocations_since_collection = 0;
+    #[cfg(feature = "unstable-global-stats")]
+    if !st.exiting {
+        global_stats_lock().insert(st.thread, st.stats.clone());
+    }
+    Some(report)
+}
+
+/// Deallocates up to `budget` boxes from the `dead` chain, or all of
+/// them if `budget` is `None`.
+///
+/// # Safety
+///
+/// `dead` must be a chain of finalized, unreachable boxes.
+unsafe fn drop_dead_boxes(dead: &mut Option<NonNull<GcBox<dyn Trace>>>, budget: Option<usize>) {
+    let _guard = DropGuard::new();
+    let mut budget = budget.unwrap_or(usize::MAX);
+    while budget > 0 {
+        let Some(node) = *dead else { break };
+        let node = unsafe { Box::from_raw(node.as_ptr()) };
+        *dead = node.header.next.take();
+        budget -= 1;
+        #[cfg(debug_assertions)]
+        DROPPING_TYPE.set(Some(node.data.gc_type_name()));
+        drop(node);
+    }
+    #[cfg(debug_assertions)]
+    DROPPING_TYPE.set(None);
+}
+
+/// Immediately triggers a garbage collection on the current thread,
+/// and returns a summary of what was collected.
+///
+/// This will panic if executed while a collection is currently in progress
+pub fn force_collect() -> CollectionReport {
+    run_pre_collect();
+    let report = GC_STATE.with(|st| {
+        let mut st = st.borrow_mut();
+        collect_garbage(&mut st, None)
+    });
+    run_deferred_finalizers();
+    run_on_collect();
+    report
+}
+
+/// Collects garbage on the current thread, unless marking takes longer
+/// than `max`.
+///
+/// If the mark phase hasn't finished after `max`, the collection is
+/// abandoned without finalizing or freeing anything, and
+/// [`CollectionOutcome::Budgeted`] is returned. This bounds the pause a
+/// collection causes, at the cost of sometimes not collecting. The time
+/// is checked between the objects marking starts from, so a single
+/// large structure reachable from one of them can overrun it. Sweeping
+/// is not limited, see `sweep_budget` in `GcConfig` for that.
+///
+/// This will panic if executed while a collection is currently in progress
+pub fn collect_with_budget(max: Duration) -> CollectionOutcome {
+    run_pre_collect();
+    let deadline = Instant::now().checked_add(max);
+    let report = GC_STATE.with(|st| {
+        let mut st = st.borrow_mut();
+        collect_garbage_until(&mut st, None, deadline)
+    });
+    match report {
+        Some(report) => {
+            run_deferred_finalizers();
+            run_on_collect();
+            CollectionOutcome::Completed(report)
+        }
+        None => CollectionOutcome::Budgeted,
+    }
+}
+
+/// The result of [`collect_with_budget`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionOutcome {
+    /// The collection finished within the budget.
+    Completed(CollectionReport),
+    /// Marking took longer than the budget, so nothing was collected.
+    Budgeted,
+}
+
+/// Like [`force_collect`], but returns an error instead of panicking if
+/// a collection is already in progress on the current thread, e.g. when
+/// called from a `Drop` impl of an object being collected.
+pub fn try_force_collect() -> Result<CollectionReport, AlreadyCollecting> {
+    if GC_STATE.with(|st| st.try_borrow_mut().is_err()) {
+        return Err(AlreadyCollecting);
+    }
+    Ok(force_collect())
+}
+
+/// An error returned by [`try_force_collect`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Hash)]
+pub struct AlreadyCollecting;
+
+impl fmt::Display for AlreadyCollecting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt("a collection is already in progress", f)
+    }
+}
+
+/// Collects garbage repeatedly until a collection frees nothing, or
+/// `max_rounds` collections have run, and returns the number of
+/// collections that ran.
+///
+/// A single collection may leave garbage behind, for example objects
+/// that a finalizer dropped the last root of, or objects whose
+/// finalizers were deferred.
+pub fn collect_until_stable(max_rounds: usize) -> usize {
+    for round in 1..=max_rounds {
+        if force_collect().objects_freed == 0 {
+            return round;
         }
-        mark(head);
-        sweep(unmarked, &mut st.stats.bytes_allocated);
     }
+    max_rounds
 }
 
-/// Immediately triggers a garbage collection on the current thread.
+/// Finalizes the objects on the current thread that are unreachable,
+/// without freeing them, and returns how many were finalized.
+///
+/// This releases the resources held by unreachable objects as soon as
+/// possible, leaving their memory to be reclaimed by the next
+/// collection. Each object is only finalized once: the next collection
+/// frees it without finalizing it again, and if a finalizer makes an
+/// object reachable again, it is not finalized when it becomes
+/// unreachable later. If `defer_finalizers` is set, the finalizers run
+/// after the unreachable objects have been found, as with any
+/// collection.
 ///
 /// This will panic if executed while a collection is currently in progress
-pub fn force_collect() {
+pub fn finalize_unreachable() -> usize {
+    let finalized = GC_STATE.with(|st| {
+        let mut st = st.borrow_mut();
+        let st = &mut *st;
+        let head = Cell::from_mut(&mut st.boxes_start);
+        unsafe {
+            let unmarked = mark(head, &st.finalize_queue);
+            finalize_unmarked(&st.config, &mut st.finalize_queue, false, &unmarked, true)
+        }
+    });
+    run_deferred_finalizers();
+    finalized
+}
+
+/// Collects garbage on the current thread, then asks the system
+/// allocator to return the memory it no longer uses to the operating
+/// system, and returns a summary of what was collected.
+///
+/// Freeing objects makes their memory available to later allocations,
+/// but the allocator usually keeps it instead of handing it back. This
+/// is useful after a spike in allocations, e.g. in a server that then
+/// goes idle. Memory is released with `malloc_trim` on Linux with
+/// glibc. On other platforms, or with another `#[global_allocator]`,
+/// this only collects.
+pub fn trim() -> CollectionReport {
+    let report = force_collect();
+    release_free_memory();
+    report
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn release_free_memory() {
+    unsafe extern "C" {
+        fn malloc_trim(pad: usize) -> i32;
+    }
+    unsafe { malloc_trim(0) };
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn release_free_memory() {}
+
+/// Runs the finalizers queued by collections while `defer_finalizers`
+/// was set. This must be called after the `GC_STATE` borrow used for
+/// the collection has been released, so that finalizers may use the
+/// collector.
+fn run_deferred_finalizers() {
+    // Removes the box whose finalizer just ran from the queue, even if
+    // the finalizer panicked.
+    struct Finalizing;
+    impl Drop for Finalizing {
+        fn drop(&mut self) {
+            GC_STATE.with(|st| {
+                let mut st = st.borrow_mut();
+                st.finalize_queue.pop_front();
+                st.finalizing = false;
+            });
+        }
+    }
+
+    loop {
+        // A finalizer may trigger a collection, which can queue more
+        // finalizers. Those are run by the outermost call.
+        let node = GC_STATE.with(|st| {
+            let mut st = st.borrow_mut();
+            if st.finalizing {
+                return None;
+            }
+            let node = *st.finalize_queue.front()?;
+            st.finalizing = true;
+            Some(node)
+        });
+        let Some(node) = node else { break };
+        let _finalizing = Finalizing;
+        let _context = FinalizeContextGuard::new(FinalizeContext {
+            thread_exit: false,
+            deferred: true,
+        });
+        // The box is kept alive by the queue until `_finalizing` is dropped.
+        unsafe { Trace::finalize_glue(&node.as_ref().data) };
+    }
+}
+
+/// Returns the number of allocated bytes above which an allocation on
+/// the current thread triggers a collection.
+///
+/// The collector raises the threshold by itself when collections don't
+/// free enough memory.
+#[must_use]
+pub fn threshold() -> usize {
+    GC_STATE.with(|st| st.borrow().config.threshold)
+}
+
+/// Sets the number of allocated bytes above which an allocation on the
+/// current thread triggers a collection.
+pub fn set_threshold(bytes: usize) {
+    GC_STATE.with(|st| st.borrow_mut().config.threshold = bytes);
+}
+
+/// Raises the collection threshold so that at least `additional` more
+/// bytes can be allocated on the current thread before an allocation
+/// triggers a collection.
+///
+/// This is useful before building a large structure that will stay
+/// alive, where collections along the way would not free anything. The
+/// threshold is never lowered, and keeps growing as usual afterwards.
+pub fn reserve(additional: usize) {
     GC_STATE.with(|st| {
         let mut st = st.borrow_mut();
-        collect_garbage(&mut st);
+        let wanted = st.stats.bytes_allocated.saturating_add(additional);
+        st.config.threshold = st.config.threshold.max(wanted);
+    });
+}
+
+/// Prevents allocations on the current thread from triggering a
+/// garbage collection until [`enable`] is called.
+///
+/// Allocations are still counted, and [`force_collect`] still works.
+pub fn disable() {
+    GC_STATE.with(|st| st.borrow_mut().auto_collect = false);
+}
+
+/// Allows allocations on the current thread to trigger a garbage
+/// collection again after a call to [`disable`].
+///
+/// If the allocation threshold was crossed while automatic collection
+/// was disabled, the next allocation will collect.
+pub fn enable() {
+    GC_STATE.with(|st| st.borrow_mut().auto_collect = true);
+}
+
+/// A guard that disables automatic collection on the current thread
+/// for as long as it is alive.
+///
+/// When dropped, it restores whether automatic collection was enabled
+/// when the guard was created, so guards can be nested.
+///
+/// The guard can't be sent to another thread, since dropping it there
+/// would resume that thread's collector instead:
+///
+/// ```compile_fail
+/// let guard = gc::GcPauseGuard::new();
+/// std::thread::spawn(move || drop(guard));
+/// ```
+#[must_use]
+pub struct GcPauseGuard {
+    was_enabled: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl GcPauseGuard {
+    /// Disables automatic collection until the guard is dropped, which
+    /// restores the previous state.
+    pub fn new() -> GcPauseGuard {
+        let was_enabled =
+            GC_STATE.with(|st| mem::replace(&mut st.borrow_mut().auto_collect, false));
+        GcPauseGuard {
+            was_enabled,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Default for GcPauseGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for GcPauseGuard {
+    fn drop(&mut self) {
+        GC_STATE.with(|st| st.borrow_mut().auto_collect = self.was_enabled);
+    }
+}
+
+/// A guard that collects garbage on the current thread when it is
+/// dropped, e.g. at the end of each iteration of an event loop.
+///
+/// Created by [`collect_on_drop`].
+#[must_use]
+pub struct CollectOnDrop {
+    _priv: (),
+}
+
+/// Returns a guard that collects garbage when it is dropped.
+///
+/// Unlike [`force_collect`], the collection is skipped if automatic
+/// collection is disabled with [`disable`], if `min_collect_interval`
+/// has not passed since the previous collection, or if a collection is
+/// already in progress.
+pub fn collect_on_drop() -> CollectOnDrop {
+    CollectOnDrop { _priv: () }
+}
+
+impl Drop for CollectOnDrop {
+    fn drop(&mut self) {
+        let skip = GC_STATE.with(|st| match st.try_borrow() {
+            Ok(st) => !st.auto_collect || st.throttled(),
+            Err(_) => true,
+        });
+        if !skip {
+            let _ = try_force_collect();
+        }
+    }
+}
+
+/// Calls the `on_collect` hook, if one is configured. This must be
+/// called after the `GC_STATE` borrow used for the collection has been
+/// released, so that the hook may use the collector itself.
+fn run_on_collect() {
+    let hook = GC_STATE.with(|st| {
+        let st = st.borrow();
+        let hook = st.config.on_collect.clone()?;
+        Some((hook, st.stats.clone()))
+    });
+    if let Some((hook, stats)) = hook {
+        hook(&stats);
+    }
+}
+
+thread_local!(static PRE_COLLECT_RUNNING: Cell<bool> = const { Cell::new(false) });
+
+/// Calls the `on_pre_collect` hook, if one is configured and it is not
+/// already running. Like `run_on_collect`, this must be called while
+/// `GC_STATE` is not borrowed.
+fn run_pre_collect() {
+    struct Running;
+    impl Drop for Running {
+        fn drop(&mut self) {
+            PRE_COLLECT_RUNNING.set(false);
+        }
+    }
+
+    if PRE_COLLECT_RUNNING.get() {
+        return;
+    }
+    let hook = GC_STATE.with(|st| {
+        let st = st.borrow();
+        let hook = st.config.on_pre_collect.clone()?;
+        Some((hook, st.stats.clone()))
     });
+    if let Some((hook, stats)) = hook {
+        PRE_COLLECT_RUNNING.set(true);
+        let _running = Running;
+        hook(&stats);
+    }
 }
 
+/// A callback invoked by the collector with its current statistics.
+pub type CollectHook = Rc<dyn Fn(&GcStats)>;
+
+#[derive(Clone)]
 pub struct GcConfig {
     pub threshold: usize,
     /// after collection we want the the ratio of used/total to be no
@@ -347,6 +1529,80 @@ pub struct GcConfig {
     /// For short-running processes it is not always appropriate to run
     /// GC, sometimes it is better to let system free the resources
     pub leak_on_drop: bool,
+    /// Called with the updated statistics after every collection, both
+    /// automatic and forced. The collector is not borrowed while the
+    /// hook runs, so it may allocate or even collect again. It is not
+    /// called for the final collection when the thread exits.
+    pub on_collect: Option<CollectHook>,
+    /// Called with the current statistics before every collection,
+    /// automatic and forced, so that references the hook drops can be
+    /// collected by that same collection. The collector is not borrowed
+    /// while the hook runs. It may allocate, but the hook is not called
+    /// again for collections started while it is running. It is not
+    /// called for the final collection when the thread exits.
+    pub on_pre_collect: Option<CollectHook>,
+    /// If set, allocations will not trigger a collection until at least
+    /// this much time has passed since the previous one, even if the
+    /// threshold has been crossed. [`force_collect`] ignores this.
+    pub min_collect_interval: Option<Duration>,
+    /// If set, an automatic collection only deallocates this many of the
+    /// unreachable objects it finds, and every later allocation
+    /// deallocates up to this many more. This spreads the cost of running
+    /// destructors over time. Unreachable objects are still finalized
+    /// during the collection, and [`force_collect`] deallocates all of
+    /// them.
+    pub sweep_budget: Option<usize>,
+    /// If set, objects found unreachable by a collection are finalized
+    /// after the collection has finished, instead of during it. Their
+    /// finalizers may then allocate, collect, or make the object
+    /// reachable again. Objects are only finalized once, and are freed by
+    /// the first collection that finds them unreachable after that.
+    pub defer_finalizers: bool,
+    /// Whether collections call `Finalize::finalize` on unreachable
+    /// objects before dropping them. If this is `false`, they are only
+    /// dropped, which also saves a second mark phase. Defaults to `true`.
+    pub run_finalizers: bool,
+    /// If set, `bytes_allocated` may not go over this many bytes. An
+    /// allocation that would cross it first runs a full collection,
+    /// even if the threshold has not been reached or
+    /// `min_collect_interval` has not passed, and panics if that does
+    /// not free enough memory (`Gc::try_new` returns an error instead).
+    /// Collections disabled with [`disable`] are not run, so allocating
+    /// over the limit then fails right away.
+    pub memory_limit: Option<usize>,
+    /// The order in which a collection finalizes the objects it found
+    /// unreachable. Defaults to [`FinalizeOrder::NewestFirst`].
+    pub finalize_order: FinalizeOrder,
+    /// If set, an allocation also triggers a collection once this many
+    /// objects have been allocated since the previous collection,
+    /// however few bytes they take up. Like the threshold, this is
+    /// subject to `min_collect_interval`.
+    pub collect_every_n_allocations: Option<usize>,
+    /// If set, the final collection when the thread exits only drops
+    /// the remaining objects, without finalizing them first, like
+    /// `run_finalizers: false`. This makes exiting faster when
+    /// finalizers are not needed at that point.
+    pub skip_finalizers_on_drop: bool,
+}
+
+/// The order in which objects found unreachable by the same collection
+/// are finalized. See [`GcConfig::finalize_order`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FinalizeOrder {
+    /// Objects are finalized from the most recently allocated to the
+    /// least recently allocated. This needs no extra work.
+    #[default]
+    NewestFirst,
+    /// Objects are finalized after the unreachable objects they refer
+    /// to, so a parent's finalizer runs after its children's. Objects
+    /// in a cycle are finalized newest first. This traces the
+    /// unreachable objects one more time to find their references.
+    ///
+    /// The final collection when the thread exits uses `NewestFirst`
+    /// instead, since the state used to trace the references may
+    /// already have been destroyed by then.
+    #[cfg(feature = "unstable-config")]
+    ChildrenFirst,
 }
 
 impl Default for GcConfig {
@@ -355,11 +1611,21 @@ impl Default for GcConfig {
             used_space_ratio: 0.7,
             threshold: 100,
             leak_on_drop: false,
+            on_collect: None,
+            on_pre_collect: None,
+            min_collect_interval: None,
+            sweep_budget: None,
+            defer_finalizers: false,
+            run_finalizers: true,
+            memory_limit: None,
+            finalize_order: FinalizeOrder::NewestFirst,
+            collect_every_n_allocations: None,
+            skip_finalizers_on_drop: false,
         }
     }
 }
 
-#[allow(dead_code)]
+#[cfg(feature = "unstable-config")]
 pub fn configure(configurer: impl FnOnce(&mut GcConfig)) {
     GC_STATE.with(|st| {
         let mut st = st.borrow_mut();
@@ -367,14 +1633,66 @@ pub fn configure(configurer: impl FnOnce(&mut GcConfig)) {
     });
 }
 
+/// Returns a copy of the current thread's collector configuration,
+/// which can be restored later with [`set_config`].
+///
+/// The `threshold` reflects any adjustment the collector has made to it
+/// since it was configured.
+#[cfg(feature = "unstable-config")]
+#[must_use]
+pub fn config_snapshot() -> GcConfig {
+    GC_STATE.with(|st| st.borrow().config.clone())
+}
+
+/// Replaces the current thread's collector configuration, for example
+/// with one saved by [`config_snapshot`].
+#[cfg(feature = "unstable-config")]
+pub fn set_config(config: GcConfig) {
+    GC_STATE.with(|st| st.borrow_mut().config = config);
+}
+
 #[derive(Clone, Default)]
 pub struct GcStats {
     pub bytes_allocated: usize,
     pub collections_performed: usize,
+    /// The number of `GcBox`es currently on this thread's chain.
+    pub live_objects: usize,
+    /// The largest value `bytes_allocated` has reached on this thread.
+    pub peak_bytes_allocated: usize,
+    /// The number of bytes allocated on this thread so far, including
+    /// those that have been freed since.
+    pub total_bytes_allocated: usize,
+    /// How long the most recent collection took, from the start of
+    /// marking to the end of sweeping.
+    pub last_collection_duration: Duration,
+    /// The time spent in all collections on this thread so far.
+    pub total_collection_time: Duration,
 }
 
-#[allow(dead_code)]
+#[cfg(any(feature = "unstable-stats", feature = "unstable-debug"))]
 #[must_use]
 pub fn stats() -> GcStats {
     GC_STATE.with(|st| st.borrow().stats.clone())
 }
+
+// Each thread's stats as of its most recent collection, for
+// `global_stats`.
+#[cfg(feature = "unstable-global-stats")]
+static GLOBAL_STATS: LazyLock<Mutex<HashMap<ThreadId, GcStats>>> = LazyLock::new(Default::default);
+
+#[cfg(feature = "unstable-global-stats")]
+fn global_stats_lock() -> MutexGuard<'static, HashMap<ThreadId, GcStats>> {
+    GLOBAL_STATS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the stats of every thread that has run a collection, as of
+/// that thread's most recent collection.
+///
+/// Unlike [`stats`], this can be called from any thread, for example
+/// one that exports metrics. A thread's entry is removed when the
+/// thread exits.
+#[cfg(feature = "unstable-global-stats")]
+#[must_use]
+pub fn global_stats() -> HashMap<ThreadId, GcStats> {
+    global_stats_lock().clone()
+}
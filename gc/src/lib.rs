@@ -11,8 +11,11 @@
 
 use crate::gc::{GcBox, GcBoxHeader};
 use std::alloc::Layout;
+use std::any::TypeId;
 use std::cell::{Cell, UnsafeCell};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::fmt::{self, Debug, Display};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
@@ -26,6 +29,8 @@ use std::marker::Unsize;
 #[cfg(feature = "nightly")]
 use std::ops::{CoerceUnsized, DispatchFromDyn};
 
+#[cfg(feature = "unstable-debug")]
+pub mod debug;
 mod gc;
 #[cfg(feature = "serde")]
 mod serde;
@@ -36,11 +41,20 @@ pub use gc_derive::{Finalize, Trace};
 
 // We re-export the Trace method, as well as some useful internal methods for
 // managing collections or configuring the garbage collector.
-pub use crate::gc::{finalizer_safe, force_collect};
+pub use crate::gc::{
+    AlreadyCollecting, CollectOnDrop, CollectionOutcome, CollectionReport, FinalizeContext,
+    GcPauseGuard, collect_on_drop, collect_until_stable, collect_with_budget, disable, enable,
+    finalize_context, finalize_unreachable, finalizer_safe, force_collect, reserve, set_threshold,
+    threshold, trim, try_force_collect,
+};
+#[doc(hidden)]
+pub use crate::trace::finalize_in_glue;
 pub use crate::trace::{Finalize, Trace};
 
+#[cfg(feature = "unstable-global-stats")]
+pub use crate::gc::global_stats;
 #[cfg(feature = "unstable-config")]
-pub use crate::gc::{GcConfig, configure};
+pub use crate::gc::{CollectHook, FinalizeOrder, GcConfig, config_snapshot, configure, set_config};
 #[cfg(feature = "unstable-stats")]
 pub use crate::gc::{GcStats, stats};
 
@@ -80,6 +94,134 @@ impl<T: Trace> Gc<T> {
     pub fn new(value: T) -> Self {
         unsafe { Gc::from_gcbox(GcBox::new(value)) }
     }
+
+    /// Constructs a new `Gc<T>` with the given value, returning an error
+    /// instead of aborting if the memory can't be allocated, or instead of
+    /// panicking if the allocation would exceed the collector's memory
+    /// limit.
+    ///
+    /// If the first allocation attempt fails, garbage is collected and
+    /// the allocation is tried again before giving up. On failure,
+    /// `value` is dropped.
+    ///
+    /// # Collection
+    ///
+    /// This method could trigger a garbage collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let five = Gc::try_new(5).unwrap();
+    /// assert_eq!(*five, 5);
+    /// ```
+    pub fn try_new(value: T) -> Result<Self, AllocError> {
+        match GcBox::try_new(value) {
+            Ok(gcbox) => Ok(unsafe { Gc::from_gcbox(gcbox) }),
+            Err(_) => Err(AllocError),
+        }
+    }
+
+    /// Returns the inner value, if `this` is the only reference to the
+    /// allocation.
+    ///
+    /// Otherwise, an `Err` is returned with the same `Gc` that was passed
+    /// in. The allocation counts as shared if there are other `Gc`
+    /// handles to it, or if any garbage-collected object (including the
+    /// value itself, through a cycle) holds a `Gc` pointing to it. It
+    /// also counts as shared while the collector has it queued for a
+    /// deferred finalizer, see `GcConfig::defer_finalizers`.
+    ///
+    /// # Collection
+    ///
+    /// This does not trigger a collection, but checking for references
+    /// from other objects traces every object on the current thread, so
+    /// it is about as expensive as the mark phase of a collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let x = Gc::new(3);
+    /// assert_eq!(Gc::try_unwrap(x), Ok(3));
+    ///
+    /// let x = Gc::new(4);
+    /// let _y = x.clone();
+    /// assert_eq!(*Gc::try_unwrap(x).unwrap_err(), 4);
+    /// ```
+    pub fn try_unwrap(this: Self) -> Result<T, Self> {
+        if !this.inner().is_unique() {
+            return Err(this);
+        }
+
+        unsafe {
+            let value = GcBox::take(NonNull::new_unchecked(this.inner_ptr()));
+            // The box is gone, so there is no root left to release.
+            mem::forget(this);
+            // The value has been moved out of the heap, so its contents
+            // are roots again.
+            value.root();
+            Ok(value)
+        }
+    }
+
+    /// Makes a handle to a part of the value, such as one of its fields,
+    /// which keeps the whole allocation alive.
+    ///
+    /// The returned [`GcProjection`] hides the type of the allocation, so
+    /// it can be handed out without exposing `T`, and needs no
+    /// allocation of its own.
+    ///
+    /// This is an associated function so that it doesn't shadow a `map`
+    /// method on `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let pair = Gc::new((1, String::from("two")));
+    /// let second = Gc::map(pair, |pair| pair.1.as_str());
+    /// assert_eq!(&*second, "two");
+    /// ```
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> GcProjection<U> {
+        let ptr = NonNull::from(f(&this));
+        // The root bit is carried over along with the address, and the
+        // root itself is handed over to the new `Gc`.
+        let this = ManuallyDrop::new(this);
+        let owner: Gc<dyn Trace> = Gc {
+            ptr_root: Cell::new(this.ptr_root.get()),
+            marker: PhantomData,
+        };
+        GcProjection { owner, ptr }
+    }
+}
+
+/// Frees the allocation `gc` points to right away, without a
+/// collection, if `gc` is the only reference to it.
+///
+/// This is [`Gc::try_unwrap`] followed by dropping the value, so the
+/// same rules decide whether the allocation is shared, and `gc` is
+/// returned in an `Err` if it is. Objects the value referred to are not
+/// freed along with it unless they become unreachable and are collected
+/// later; finding out whether they are referenced from elsewhere would
+/// take a full mark phase, which is what [`force_collect`] does.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, collect_if_unreferenced};
+///
+/// let x = Gc::new(1);
+/// let y = x.clone();
+/// let x = collect_if_unreferenced(x).unwrap_err();
+/// drop(y);
+/// assert!(collect_if_unreferenced(x).is_ok());
+/// ```
+pub fn collect_if_unreferenced<T: Trace>(gc: Gc<T>) -> Result<(), Gc<T>> {
+    Gc::try_unwrap(gc).map(drop)
 }
 
 impl<T: Trace + ?Sized> Gc<T> {
@@ -105,10 +247,91 @@ impl<T: Trace + ?Sized> Gc<T> {
             gc
         }
     }
+
+    /// Returns a mutable reference to the value, if `this` is the only
+    /// reference to the allocation.
+    ///
+    /// Returns `None` if there are other `Gc` handles to the allocation,
+    /// or if any garbage-collected object (including the value itself,
+    /// through a cycle) holds a `Gc` pointing to it. This is the same
+    /// check as [`Gc::try_unwrap`], and is just as expensive.
+    ///
+    /// The `Gc`s inside the value are rooted while the returned
+    /// `GcRefMut` is alive, so they can be moved out of it safely.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let mut x = Gc::new(3);
+    /// *Gc::get_mut(&mut x).unwrap() = 4;
+    /// assert_eq!(*x, 4);
+    ///
+    /// let _y = x.clone();
+    /// assert!(Gc::get_mut(&mut x).is_none());
+    /// ```
+    pub fn get_mut(this: &mut Self) -> Option<GcRefMut<'_, T>> {
+        if !this.inner().is_unique() {
+            return None;
+        }
+        Some(Gc::borrow_unique(this))
+    }
+
+    /// Returns a mutable reference to the value, cloning it into a new
+    /// allocation first if `this` is not the only reference to it.
+    ///
+    /// This is the copy-on-write counterpart of [`Gc::get_mut`], like
+    /// `Rc::make_mut`. Other `Gc`s to the old allocation keep seeing the
+    /// old value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let mut x = Gc::new(3);
+    /// let y = x.clone();
+    /// *Gc::make_mut(&mut x) += 1;
+    /// *Gc::make_mut(&mut x) += 1;
+    /// assert_eq!((*x, *y), (5, 3));
+    /// ```
+    pub fn make_mut(this: &mut Self) -> GcRefMut<'_, T>
+    where
+        T: Clone,
+    {
+        if !this.inner().is_unique() {
+            *this = Gc::new(T::clone(this));
+        }
+        Gc::borrow_unique(this)
+    }
+
+    // Borrows the value of a `Gc` that is known to be unique.
+    fn borrow_unique(this: &mut Self) -> GcRefMut<'_, T> {
+        let inner = this.inner();
+        unsafe { inner.value().root() };
+        inner.header().set_borrowed(true);
+        GcRefMut { gc: this }
+    }
 }
 
 impl<T: ?Sized> Gc<T> {
     /// Returns `true` if the two `Gc`s point to the same allocation.
+    ///
+    /// This compares identity, unlike `==`, which compares the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let five = Gc::new(5);
+    /// let same_five = five.clone();
+    /// let other_five = Gc::new(5);
+    ///
+    /// assert!(Gc::ptr_eq(&five, &same_five));
+    /// assert!(!Gc::ptr_eq(&five, &other_five));
+    /// ```
     pub fn ptr_eq(this: &Gc<T>, other: &Gc<T>) -> bool {
         GcBox::ptr_eq(this.inner(), other.inner())
     }
@@ -130,6 +353,135 @@ impl<T: ?Sized> Gc<T> {
         let ptr = this.inner_ptr();
         GcBox::value_ptr(ptr)
     }
+
+    /// Returns the number of roots keeping the allocation alive.
+    ///
+    /// Every `Gc` that is not stored inside another garbage-collected
+    /// object counts as a root. `Gc`s stored inside garbage-collected
+    /// objects are not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let x = Gc::new(5);
+    /// let y = x.clone();
+    /// assert_eq!(Gc::root_count(&x), 2);
+    ///
+    /// let z = Gc::new(y);
+    /// assert_eq!(Gc::root_count(&x), 1);
+    /// # drop(z);
+    /// ```
+    pub fn root_count(this: &Gc<T>) -> usize {
+        this.inner().header().roots()
+    }
+
+    /// Returns the tag stored with the allocation. It is 0 until it is
+    /// changed with [`Gc::set_tag`].
+    ///
+    /// The tag is a byte of the allocation's header that the collector
+    /// doesn't use, so it can hold a type discriminant or similar
+    /// without adding a field to the value. It is shared by every `Gc`
+    /// pointing to the allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let x = Gc::new("abc");
+    /// let y = x.clone();
+    /// assert_eq!(Gc::tag(&x), 0);
+    /// Gc::set_tag(&x, 7);
+    /// assert_eq!(Gc::tag(&y), 7);
+    /// ```
+    pub fn tag(this: &Gc<T>) -> u8 {
+        this.inner().header().tag()
+    }
+
+    /// Sets the tag stored with the allocation, see [`Gc::tag`].
+    pub fn set_tag(this: &Gc<T>, tag: u8) {
+        this.inner().header().set_tag(tag);
+    }
+
+    /// Returns a reference to the value, or `None` if dereferencing the
+    /// `Gc` would panic because it is not rooted and the collector is
+    /// dropping garbage.
+    ///
+    /// This can only happen in a `Drop` impl of a garbage-collected
+    /// object, where the value may already have been freed. Code that
+    /// may run there can use this to skip the value instead of
+    /// panicking. See [`finalizer_safe`].
+    ///
+    /// This is an associated function so that it doesn't shadow a
+    /// `try_deref` method on `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let x = Gc::new(5);
+    /// assert_eq!(Gc::try_deref(&x), Some(&5));
+    /// ```
+    pub fn try_deref(this: &Gc<T>) -> Option<&T> {
+        if finalizer_safe() || this.rooted() {
+            Some(this.inner().value())
+        } else {
+            None
+        }
+    }
+
+    /// Makes a clone of the `Gc` pointer, returning an error instead of
+    /// panicking if the allocation's root count would overflow.
+    ///
+    /// The root count is limited to `usize::MAX >> 3`, so this can only
+    /// fail if that many `Gc`s have been leaked, e.g. with `mem::forget`.
+    ///
+    /// This is an associated function so that it doesn't shadow a
+    /// `try_clone` method on `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::Gc;
+    ///
+    /// let x = Gc::new(5);
+    /// let y = Gc::try_clone(&x).unwrap();
+    /// assert!(Gc::ptr_eq(&x, &y));
+    /// ```
+    pub fn try_clone(this: &Gc<T>) -> Result<Self, RootOverflow> {
+        if !unsafe { this.inner().try_root_inner() } {
+            return Err(RootOverflow);
+        }
+        let gc = Gc {
+            ptr_root: Cell::new(this.ptr_root.get()),
+            marker: PhantomData,
+        };
+        unsafe { gc.set_root() };
+        Ok(gc)
+    }
+}
+
+/// An error returned by [`Gc::try_clone`](struct.Gc.html#method.try_clone).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Hash)]
+pub struct RootOverflow;
+
+impl Display for RootOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt("roots counter overflow", f)
+    }
+}
+
+/// An error returned by [`Gc::try_new`](struct.Gc.html#method.try_new).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Hash)]
+pub struct AllocError;
+
+impl Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt("memory allocation failed", f)
+    }
 }
 
 /// Returns the given pointer with its root bit cleared.
@@ -143,6 +495,25 @@ unsafe fn clear_root_bit<T: ?Sized>(ptr: NonNull<GcBox<T>>) -> NonNull<GcBox<T>>
     }
 }
 
+/// Panics for an unrooted `Gc<T>` dereferenced while garbage is being
+/// dropped, naming the value whose `Drop` impl did it when that is
+/// known.
+#[cold]
+#[inline(never)]
+fn unrooted_access<T: ?Sized>() -> ! {
+    let target = std::any::type_name::<T>();
+    match crate::gc::dropping_type() {
+        Some(dropping) => panic!(
+            "Gc<{target}> dereferenced while the collector was dropping a \
+             `{dropping}`; Drop impls of traced types must not access Gc pointers"
+        ),
+        None => panic!(
+            "Gc<{target}> dereferenced while the collector was dropping garbage; \
+             Drop impls of traced types must not access Gc pointers"
+        ),
+    }
+}
+
 impl<T: ?Sized> Gc<T> {
     fn rooted(&self) -> bool {
         self.ptr_root.get().as_ptr().cast::<u8>() as usize & 1 != 0
@@ -169,8 +540,10 @@ impl<T: ?Sized> Gc<T> {
         // By opting into `Trace` you agree to not dereference this pointer
         // within your drop method, meaning that it should be safe.
         //
-        // This assert exists just in case.
-        assert!(finalizer_safe() || self.rooted());
+        // This check exists just in case.
+        if !(finalizer_safe() || self.rooted()) {
+            unrooted_access::<T>();
+        }
 
         unsafe { clear_root_bit(self.ptr_root.get()).as_ptr() }
     }
@@ -184,8 +557,10 @@ impl<T: ?Sized> Gc<T> {
 impl<T: ?Sized> Gc<T> {
     /// Consumes the `Gc`, returning the wrapped pointer.
     ///
-    /// To avoid a memory leak, the pointer must be converted back into a `Gc`
-    /// using [`Gc::from_raw`][from_raw].
+    /// The root held by `this` is kept, so the allocation stays alive
+    /// until the pointer is converted back into a `Gc` using
+    /// [`Gc::from_raw`][from_raw]. Not doing so leaks the allocation, and
+    /// everything reachable from it.
     ///
     /// [from_raw]: struct.Gc.html#method.from_raw
     ///
@@ -236,6 +611,11 @@ impl<T: ?Sized> Gc<T> {
     ///
     /// # Safety
     ///
+    /// `ptr` must have been returned by [`Gc::into_raw`][into_raw] on the
+    /// current thread, with `T` being the same type it was returned
+    /// for. Each such pointer may be passed to `from_raw` at most once,
+    /// since every call takes over the root that `into_raw` kept. Passing
+    /// any other pointer is undefined behavior.
     pub unsafe fn from_raw(ptr: *const T) -> Self {
         unsafe {
             // Find the offset of T in GcBox<T>. Note that Layout::extend
@@ -258,6 +638,39 @@ impl<T: ?Sized> Gc<T> {
     }
 }
 
+impl Gc<dyn Trace> {
+    /// Attempts to downcast the `Gc<dyn Trace>` to a concrete type.
+    ///
+    /// Returns the same `Gc` in the `Err` variant if the value is not a
+    /// `T`.
+    pub fn downcast<T: Trace>(self) -> Result<Gc<T>, Self> {
+        if self.inner().value().gc_type_id() != TypeId::of::<T>() {
+            return Err(self);
+        }
+
+        // The root bit is carried over along with the address, and the
+        // root itself is handed over to the new `Gc`.
+        let this = ManuallyDrop::new(self);
+        Ok(Gc {
+            ptr_root: Cell::new(this.ptr_root.get().cast::<GcBox<T>>()),
+            marker: PhantomData,
+        })
+    }
+
+    /// Returns a reference to the value if it is a `T`, without
+    /// consuming the `Gc`.
+    ///
+    /// To get mutable access, borrow the value uniquely with
+    /// [`Gc::get_mut`] and use [`GcRefMut::downcast_mut`].
+    pub fn downcast_ref<T: Trace + 'static>(&self) -> Option<&T> {
+        let value = self.inner().value();
+        if value.gc_type_id() != TypeId::of::<T>() {
+            return None;
+        }
+        Some(unsafe { &*ptr::from_ref(value).cast::<T>() })
+    }
+}
+
 impl<T: ?Sized> Finalize for Gc<T> {}
 
 unsafe impl<T: Trace + ?Sized> Trace for Gc<T> {
@@ -290,153 +703,707 @@ unsafe impl<T: Trace + ?Sized> Trace for Gc<T> {
             // phase, and we don't want to modify our state before panicking.
             self.inner().unroot_inner();
 
-            self.clear_root()
-        };
+            self.clear_root()
+        };
+    }
+
+    #[inline]
+    fn finalize_glue(&self) {
+        finalize_in_glue(self);
+    }
+}
+
+impl<T: ?Sized> Clone for Gc<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        unsafe { self.inner().root_inner() };
+        let gc = Gc {
+            ptr_root: Cell::new(self.ptr_root.get()),
+            marker: PhantomData,
+        };
+        unsafe { gc.set_root() };
+        gc
+    }
+
+    /// Makes `self` point to the same allocation as `source`. This does
+    /// nothing if they already point to the same allocation, instead of
+    /// unrooting and re-rooting it.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        if !Gc::ptr_eq(self, source) {
+            *self = source.clone();
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Gc<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.inner().value()
+    }
+}
+
+impl<T: ?Sized> Drop for Gc<T> {
+    #[inline]
+    fn drop(&mut self) {
+        // If this pointer was a root, we should unroot it.
+        if self.rooted() {
+            unsafe { self.inner().unroot_inner() };
+        }
+    }
+}
+
+impl<T: Trace + Default> Default for Gc<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for Gc<T> {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for Gc<T> {}
+
+impl<T: ?Sized + PartialOrd> PartialOrd for Gc<T> {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+
+    #[inline(always)]
+    fn lt(&self, other: &Self) -> bool {
+        **self < **other
+    }
+
+    #[inline(always)]
+    fn le(&self, other: &Self) -> bool {
+        **self <= **other
+    }
+
+    #[inline(always)]
+    fn gt(&self, other: &Self) -> bool {
+        **self > **other
+    }
+
+    #[inline(always)]
+    fn ge(&self, other: &Self) -> bool {
+        **self >= **other
+    }
+}
+
+impl<T: ?Sized + Ord> Ord for Gc<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for Gc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl<T: ?Sized + Display> Display for Gc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Gc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for Gc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.inner(), f)
+    }
+}
+
+impl<T: Trace> From<T> for Gc<T> {
+    fn from(t: T) -> Self {
+        Self::new(t)
+    }
+}
+
+impl<
+    #[cfg(not(feature = "nightly"))] T: Trace,
+    #[cfg(feature = "nightly")] T: Trace + Unsize<dyn Trace> + ?Sized,
+> From<Box<T>> for Gc<T>
+{
+    /// Moves a boxed value into a new garbage-collected
+    /// allocation. If the `nightly` crate feature is enabled, the
+    /// value may be an unsized trait object.
+    fn from(v: Box<T>) -> Gc<T> {
+        unsafe { Gc::from_gcbox(GcBox::from_box(v)) }
+    }
+}
+
+impl<T: ?Sized> std::borrow::Borrow<T> for Gc<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> std::convert::AsRef<T> for Gc<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+/// A wrapper type for a mutably borrowed value from a `Gc<T>`,
+/// returned by [`Gc::get_mut`].
+pub struct GcRefMut<'a, T: Trace + ?Sized + 'static> {
+    gc: &'a mut Gc<T>,
+}
+
+impl<T: Trace + ?Sized> Deref for GcRefMut<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.gc.inner().value()
+    }
+}
+
+impl<T: Trace + ?Sized> DerefMut for GcRefMut<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        // The collector doesn't read the data while the box is marked as
+        // borrowed, and no other handle to it exists.
+        unsafe { &mut *GcBox::value_ptr(self.gc.inner_ptr()).cast_mut() }
+    }
+}
+
+impl GcRefMut<'_, dyn Trace> {
+    /// Returns a mutable reference to the borrowed value if it is a `T`.
+    pub fn downcast_mut<T: Trace + 'static>(&mut self) -> Option<&mut T> {
+        if self.gc_type_id() != TypeId::of::<T>() {
+            return None;
+        }
+        Some(unsafe { &mut *ptr::from_mut(&mut **self).cast::<T>() })
+    }
+}
+
+impl<T: Trace + ?Sized> Drop for GcRefMut<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        let inner = self.gc.inner();
+        inner.header().set_borrowed(false);
+        // The value is back on the heap, so its contents are no longer
+        // roots.
+        unsafe { inner.value().unroot() };
+    }
+}
+
+impl<T: Trace + ?Sized + Debug> Debug for GcRefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+//////////////////
+// GcProjection //
+//////////////////
+
+/// A handle to a part of a garbage-collected value, created by
+/// [`Gc::map`].
+///
+/// It dereferences to the part, and keeps the `Gc` it was made from,
+/// so the whole allocation stays alive for as long as it does. Like a
+/// `Gc`, it is traced when stored in a garbage-collected object.
+pub struct GcProjection<U: ?Sized> {
+    owner: Gc<dyn Trace>,
+    ptr: NonNull<U>,
+}
+
+impl<U: ?Sized> GcProjection<U> {
+    /// Makes a handle to a part of the projected value, keeping the same
+    /// allocation alive.
+    ///
+    /// This is an associated function so that it doesn't shadow a `map`
+    /// method on `U`.
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&U) -> &V) -> GcProjection<V> {
+        let ptr = NonNull::from(f(&this));
+        GcProjection {
+            owner: this.owner,
+            ptr,
+        }
+    }
+
+    /// Returns the `Gc` of the allocation the projection points into.
+    pub fn owner(this: &Self) -> &Gc<dyn Trace> {
+        &this.owner
+    }
+}
+
+impl<U: ?Sized> Deref for GcProjection<U> {
+    type Target = U;
+
+    #[inline]
+    fn deref(&self) -> &U {
+        // Go through the owner, so that dereferencing an unrooted
+        // projection while garbage is being dropped panics like it does
+        // for a `Gc`.
+        let _ = &*self.owner;
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<U: ?Sized> Clone for GcProjection<U> {
+    fn clone(&self) -> Self {
+        GcProjection {
+            owner: self.owner.clone(),
+            ptr: self.ptr,
+        }
+    }
+}
+
+impl<U: ?Sized + Debug> Debug for GcProjection<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized + Display> Display for GcProjection<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized> Finalize for GcProjection<U> {}
+
+unsafe impl<U: ?Sized> Trace for GcProjection<U> {
+    custom_trace!(this, mark(&this.owner));
+}
+
+/////////////////
+// GcByAddress //
+/////////////////
+
+// The address identifying a `Gc`'s allocation, ignoring metadata.
+fn address<T: ?Sized>(gc: &Gc<T>) -> usize {
+    Gc::as_ptr(gc).cast::<()>() as usize
+}
+
+/// A wrapper around a `Gc<T>` that compares, orders and hashes by the
+/// address of the allocation rather than by value.
+///
+/// This allows `Gc`s to be used as identity keys in maps and sets,
+/// including ordered ones like `BTreeMap`. The address doesn't depend
+/// on whether the `Gc` is rooted, and doesn't change while the
+/// allocation is alive, but the order is otherwise arbitrary.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, GcByAddress};
+/// use std::collections::HashSet;
+///
+/// let a = Gc::new(1);
+/// let b = Gc::new(1);
+///
+/// let mut seen = HashSet::new();
+/// assert!(seen.insert(GcByAddress(a.clone())));
+/// assert!(seen.insert(GcByAddress(b)));
+/// assert!(!seen.insert(GcByAddress(a)));
+/// ```
+pub struct GcByAddress<T: ?Sized + 'static>(pub Gc<T>);
+
+impl<T: ?Sized> Clone for GcByAddress<T> {
+    fn clone(&self) -> Self {
+        GcByAddress(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> PartialEq for GcByAddress<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Gc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: ?Sized> Eq for GcByAddress<T> {}
+
+impl<T: ?Sized> PartialOrd for GcByAddress<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized> Ord for GcByAddress<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        address(&self.0).cmp(&address(&other.0))
+    }
+}
+
+impl<T: ?Sized> Hash for GcByAddress<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Ignore the metadata of unsized values, like `Gc::ptr_eq` does.
+        address(&self.0).hash(state);
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for GcByAddress<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T: ?Sized> Deref for GcByAddress<T> {
+    type Target = Gc<T>;
+
+    fn deref(&self) -> &Gc<T> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> From<Gc<T>> for GcByAddress<T> {
+    fn from(gc: Gc<T>) -> Self {
+        GcByAddress(gc)
+    }
+}
+
+impl<T: ?Sized> Finalize for GcByAddress<T> {}
+
+unsafe impl<T: Trace + ?Sized> Trace for GcByAddress<T> {
+    custom_trace!(this, mark(&this.0));
+}
+
+/////////////////////
+// GcSet and GcMap //
+/////////////////////
+
+/// A set of `Gc<T>`s that compares them by the address of their
+/// allocation, like [`GcByAddress`], rather than by value.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, GcSet};
+///
+/// let a = Gc::new(1);
+/// let b = Gc::new(1);
+///
+/// let mut visited = GcSet::new();
+/// assert!(visited.insert(a.clone()));
+/// assert!(!visited.insert(a.clone()));
+/// assert!(visited.contains(&a));
+/// assert!(!visited.contains(&b));
+/// ```
+pub struct GcSet<T: ?Sized + 'static> {
+    map: HashMap<usize, Gc<T>>,
+}
+
+impl<T: ?Sized> GcSet<T> {
+    /// Creates an empty set.
+    #[must_use]
+    pub fn new() -> Self {
+        GcSet {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Adds `gc` to the set. Returns `true` if its allocation was not in
+    /// the set yet.
+    pub fn insert(&mut self, gc: Gc<T>) -> bool {
+        match self.map.entry(address(&gc)) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(gc);
+                true
+            }
+        }
+    }
+
+    /// Returns `true` if the set contains a `Gc` to the same allocation
+    /// as `gc`.
+    pub fn contains(&self, gc: &Gc<T>) -> bool {
+        self.map.contains_key(&address(gc))
+    }
+
+    /// Removes the `Gc` to the same allocation as `gc` from the set, and
+    /// returns it.
+    pub fn remove(&mut self, gc: &Gc<T>) -> Option<Gc<T>> {
+        self.map.remove(&address(gc))
+    }
+
+    /// Returns an iterator over the `Gc`s in the set, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = &Gc<T>> {
+        self.map.values()
+    }
+
+    /// Returns the number of `Gc`s in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
     }
 
-    #[inline]
-    fn finalize_glue(&self) {
-        Finalize::finalize(self);
+    /// Removes every `Gc` from the set.
+    pub fn clear(&mut self) {
+        self.map.clear();
     }
 }
 
-impl<T: ?Sized> Clone for Gc<T> {
-    #[inline]
+impl<T: ?Sized> Default for GcSet<T> {
+    fn default() -> Self {
+        GcSet::new()
+    }
+}
+
+impl<T: ?Sized> Clone for GcSet<T> {
     fn clone(&self) -> Self {
-        unsafe { self.inner().root_inner() };
-        let gc = Gc {
-            ptr_root: Cell::new(self.ptr_root.get()),
-            marker: PhantomData,
-        };
-        unsafe { gc.set_root() };
-        gc
+        GcSet {
+            map: self.map.clone(),
+        }
     }
 }
 
-impl<T: ?Sized> Deref for Gc<T> {
-    type Target = T;
+impl<T: ?Sized + Debug> Debug for GcSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
 
-    #[inline]
-    fn deref(&self) -> &T {
-        self.inner().value()
+impl<T: ?Sized> FromIterator<Gc<T>> for GcSet<T> {
+    fn from_iter<I: IntoIterator<Item = Gc<T>>>(iter: I) -> Self {
+        let mut set = GcSet::new();
+        set.extend(iter);
+        set
     }
 }
 
-impl<T: ?Sized> Drop for Gc<T> {
-    #[inline]
-    fn drop(&mut self) {
-        // If this pointer was a root, we should unroot it.
-        if self.rooted() {
-            unsafe { self.inner().unroot_inner() };
+impl<T: ?Sized> Extend<Gc<T>> for GcSet<T> {
+    fn extend<I: IntoIterator<Item = Gc<T>>>(&mut self, iter: I) {
+        for gc in iter {
+            self.insert(gc);
         }
     }
 }
 
-impl<T: Trace + Default> Default for Gc<T> {
-    #[inline]
-    fn default() -> Self {
-        Self::new(Default::default())
-    }
+impl<T: ?Sized> Finalize for GcSet<T> {}
+
+unsafe impl<T: Trace + ?Sized> Trace for GcSet<T> {
+    custom_trace!(this, mark(&this.map));
 }
 
-impl<T: ?Sized + PartialEq> PartialEq for Gc<T> {
-    #[inline(always)]
-    fn eq(&self, other: &Self) -> bool {
-        **self == **other
-    }
+/// A map keyed by `Gc<K>`s that compares them by the address of their
+/// allocation, like [`GcByAddress`], rather than by value.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, GcMap};
+///
+/// let a = Gc::new("a");
+/// let b = Gc::new("a");
+///
+/// let mut depth = GcMap::new();
+/// depth.insert(a.clone(), 0);
+/// depth.insert(b.clone(), 1);
+/// assert_eq!(depth.get(&a), Some(&0));
+/// assert_eq!(depth.get(&b), Some(&1));
+/// ```
+pub struct GcMap<K: ?Sized + 'static, V> {
+    map: HashMap<usize, (Gc<K>, V)>,
 }
 
-impl<T: ?Sized + Eq> Eq for Gc<T> {}
+impl<K: ?Sized, V> GcMap<K, V> {
+    /// Creates an empty map.
+    #[must_use]
+    pub fn new() -> Self {
+        GcMap {
+            map: HashMap::new(),
+        }
+    }
 
-impl<T: ?Sized + PartialOrd> PartialOrd for Gc<T> {
-    #[inline(always)]
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (**self).partial_cmp(&**other)
+    /// Inserts `value` for the allocation `key` points to, returning the
+    /// previous value for it, if any.
+    pub fn insert(&mut self, key: Gc<K>, value: V) -> Option<V> {
+        self.map
+            .insert(address(&key), (key, value))
+            .map(|(_, old)| old)
     }
 
-    #[inline(always)]
-    fn lt(&self, other: &Self) -> bool {
-        **self < **other
+    /// Returns the value for the allocation `key` points to.
+    pub fn get(&self, key: &Gc<K>) -> Option<&V> {
+        self.map.get(&address(key)).map(|(_, value)| value)
     }
 
-    #[inline(always)]
-    fn le(&self, other: &Self) -> bool {
-        **self <= **other
+    /// Returns a mutable reference to the value for the allocation `key`
+    /// points to.
+    pub fn get_mut(&mut self, key: &Gc<K>) -> Option<&mut V> {
+        self.map.get_mut(&address(key)).map(|(_, value)| value)
     }
 
-    #[inline(always)]
-    fn gt(&self, other: &Self) -> bool {
-        **self > **other
+    /// Returns `true` if the map has a value for the allocation `key`
+    /// points to.
+    pub fn contains_key(&self, key: &Gc<K>) -> bool {
+        self.map.contains_key(&address(key))
     }
 
-    #[inline(always)]
-    fn ge(&self, other: &Self) -> bool {
-        **self >= **other
+    /// Removes the entry for the allocation `key` points to, and returns
+    /// its key and value.
+    pub fn remove(&mut self, key: &Gc<K>) -> Option<(Gc<K>, V)> {
+        self.map.remove(&address(key))
     }
-}
 
-impl<T: ?Sized + Ord> Ord for Gc<T> {
-    #[inline]
-    fn cmp(&self, other: &Self) -> Ordering {
-        (**self).cmp(&**other)
+    /// Returns an iterator over the entries of the map, in arbitrary
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Gc<K>, &V)> {
+        self.map.values().map(|(key, value)| (key, value))
+    }
+
+    /// Returns the number of entries in the map.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the map is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes every entry from the map.
+    pub fn clear(&mut self) {
+        self.map.clear();
     }
 }
 
-impl<T: ?Sized + Hash> Hash for Gc<T> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        (**self).hash(state);
+impl<K: ?Sized, V> Default for GcMap<K, V> {
+    fn default() -> Self {
+        GcMap::new()
     }
 }
 
-impl<T: ?Sized + Display> Display for Gc<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Display::fmt(&**self, f)
+impl<K: ?Sized, V: Clone> Clone for GcMap<K, V> {
+    fn clone(&self) -> Self {
+        GcMap {
+            map: self.map.clone(),
+        }
     }
 }
 
-impl<T: ?Sized + Debug> Debug for Gc<T> {
+impl<K: ?Sized + Debug, V: Debug> Debug for GcMap<K, V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Debug::fmt(&**self, f)
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
-impl<T: ?Sized> fmt::Pointer for Gc<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Pointer::fmt(&self.inner(), f)
+impl<K: ?Sized, V> FromIterator<(Gc<K>, V)> for GcMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (Gc<K>, V)>>(iter: I) -> Self {
+        let mut map = GcMap::new();
+        map.extend(iter);
+        map
     }
 }
 
-impl<T: Trace> From<T> for Gc<T> {
-    fn from(t: T) -> Self {
-        Self::new(t)
+impl<K: ?Sized, V> Extend<(Gc<K>, V)> for GcMap<K, V> {
+    fn extend<I: IntoIterator<Item = (Gc<K>, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
     }
 }
 
-impl<
-    #[cfg(not(feature = "nightly"))] T: Trace,
-    #[cfg(feature = "nightly")] T: Trace + Unsize<dyn Trace> + ?Sized,
-> From<Box<T>> for Gc<T>
-{
-    /// Moves a boxed value into a new garbage-collected
-    /// allocation. If the `nightly` crate feature is enabled, the
-    /// value may be an unsized trait object.
-    fn from(v: Box<T>) -> Gc<T> {
-        unsafe { Gc::from_gcbox(GcBox::from_box(v)) }
+impl<K: ?Sized, V> Finalize for GcMap<K, V> {}
+
+unsafe impl<K: Trace + ?Sized, V: Trace> Trace for GcMap<K, V> {
+    custom_trace!(this, mark(&this.map));
+}
+
+///////////////
+// GcClosure //
+///////////////
+
+/// A callback that can hold `Gc`s and be stored in garbage-collected
+/// objects.
+///
+/// Closures can't implement `Trace`, because the collector can't see
+/// what they capture. A `GcClosure` keeps its captured state in a
+/// `Trace` value instead, and its code is a function pointer that
+/// receives that state. Non-capturing closures coerce to function
+/// pointers, so the state has to be passed explicitly. Several
+/// arguments can be passed as a tuple.
+///
+/// Because the state is traced, a `GcClosure` stored in an object it
+/// refers to forms a cycle that is collected like any other.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, GcCell, GcClosure};
+///
+/// let count = Gc::new(GcCell::new(0));
+/// let add = GcClosure::new(count.clone(), |count, n: i32| {
+///     *count.borrow_mut() += n;
+/// });
+/// add.call(2);
+/// add.call(3);
+/// assert_eq!(*count.borrow(), 5);
+/// ```
+pub struct GcClosure<C, A, R = ()> {
+    captures: C,
+    f: fn(&C, A) -> R,
+}
+
+impl<C: Trace, A, R> GcClosure<C, A, R> {
+    /// Creates a callback that calls `f` with `captures` and its
+    /// argument.
+    pub fn new(captures: C, f: fn(&C, A) -> R) -> Self {
+        GcClosure { captures, f }
+    }
+
+    /// Calls the callback.
+    pub fn call(&self, args: A) -> R {
+        (self.f)(&self.captures, args)
+    }
+
+    /// Returns the captured state.
+    pub fn captures(&self) -> &C {
+        &self.captures
     }
 }
 
-impl<T: ?Sized> std::borrow::Borrow<T> for Gc<T> {
-    fn borrow(&self) -> &T {
-        self
+impl<C: Clone, A, R> Clone for GcClosure<C, A, R> {
+    fn clone(&self) -> Self {
+        GcClosure {
+            captures: self.captures.clone(),
+            f: self.f,
+        }
     }
 }
 
-impl<T: ?Sized> std::convert::AsRef<T> for Gc<T> {
-    fn as_ref(&self) -> &T {
-        self
+impl<C: Debug, A, R> Debug for GcClosure<C, A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcClosure")
+            .field("captures", &self.captures)
+            .finish_non_exhaustive()
     }
 }
 
+impl<C, A, R> Finalize for GcClosure<C, A, R> {}
+
+unsafe impl<C: Trace, A, R> Trace for GcClosure<C, A, R> {
+    custom_trace!(this, mark(&this.captures));
+}
+
 ////////////
 // GcCell //
 ////////////
@@ -451,10 +1418,15 @@ impl<T: ?Sized> std::convert::AsRef<T> for Gc<T> {
 #[derive(Copy, Clone)]
 struct BorrowFlag(usize);
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum BorrowState {
+/// Whether a [`GcCell`] is currently borrowed, as returned by
+/// [`GcCell::borrow_state`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BorrowState {
+    /// The cell is immutably borrowed.
     Reading,
+    /// The cell is mutably borrowed.
     Writing,
+    /// The cell is not borrowed.
     Unused,
 }
 
@@ -542,6 +1514,38 @@ impl<T> GcCell<T> {
 }
 
 impl<T: ?Sized> GcCell<T> {
+    /// Returns whether the value is currently borrowed, without
+    /// borrowing it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::{BorrowState, GcCell};
+    ///
+    /// let c = GcCell::new(5);
+    /// assert_eq!(c.borrow_state(), BorrowState::Unused);
+    ///
+    /// let m = c.borrow_mut();
+    /// assert_eq!(c.borrow_state(), BorrowState::Writing);
+    /// ```
+    #[inline]
+    pub fn borrow_state(&self) -> BorrowState {
+        self.flags.get().borrowed()
+    }
+
+    /// Returns `true` if the value is currently borrowed, either
+    /// mutably or immutably.
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        self.borrow_state() != BorrowState::Unused
+    }
+
+    /// Returns `true` if the value is currently mutably borrowed.
+    #[inline]
+    pub fn is_borrowed_mut(&self) -> bool {
+        self.borrow_state() == BorrowState::Writing
+    }
+
     /// Immutably borrows the wrapped value.
     ///
     /// The borrow lasts until the returned `GcCellRef` exits scope.
@@ -665,6 +1669,80 @@ impl<T: Trace + ?Sized> GcCell<T> {
     }
 }
 
+// These all go through `borrow_mut`, which roots the contents for the
+// duration of the borrow, so the value moved out is rooted and the value
+// moved in is unrooted again if the `GcCell` is.
+impl<T: Trace> GcCell<T> {
+    /// Replaces the wrapped value with a new one, returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::GcCell;
+    ///
+    /// let c = GcCell::new(5);
+    /// assert_eq!(c.replace(6), 5);
+    /// assert_eq!(*c.borrow(), 6);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn replace(&self, t: T) -> T {
+        mem::replace(&mut *self.borrow_mut(), t)
+    }
+
+    /// Replaces the wrapped value with a new one computed from `f`,
+    /// returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::GcCell;
+    ///
+    /// let c = GcCell::new(5);
+    /// assert_eq!(c.replace_with(|&mut old| old + 1), 5);
+    /// assert_eq!(*c.borrow(), 6);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn replace_with<F: FnOnce(&mut T) -> T>(&self, f: F) -> T {
+        let mut value = self.borrow_mut();
+        let new = f(&mut value);
+        mem::replace(&mut *value, new)
+    }
+
+    /// Swaps the wrapped value of `self` with the wrapped value of `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value in either `GcCell` is currently borrowed, or
+    /// if `self` and `other` are the same `GcCell`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use gc::GcCell;
+    ///
+    /// let c = GcCell::new(5);
+    /// let d = GcCell::new(6);
+    /// c.swap(&d);
+    /// assert_eq!(*c.borrow(), 6);
+    /// assert_eq!(*d.borrow(), 5);
+    /// ```
+    #[inline]
+    #[track_caller]
+    pub fn swap(&self, other: &Self) {
+        mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut());
+    }
+}
+
 /// An error returned by [`GcCell::try_borrow`](struct.GcCell.html#method.try_borrow).
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Hash)]
 pub struct BorrowError;
@@ -720,7 +1798,7 @@ unsafe impl<T: Trace + ?Sized> Trace for GcCell<T> {
 
     #[inline]
     fn finalize_glue(&self) {
-        Finalize::finalize(self);
+        finalize_in_glue(self);
         match self.flags.get().borrowed() {
             BorrowState::Writing => (),
             _ => unsafe { (*self.cell.get()).finalize_glue() },
@@ -1123,6 +2201,134 @@ impl<T: ?Sized + Debug> Debug for GcCell<T> {
     }
 }
 
+///////////////
+// GcCowCell //
+///////////////
+
+/// A mutable memory location with copy-on-write semantics, so that it
+/// can be modified while it is being read.
+///
+/// Reading a `GcCowCell` returns a snapshot of its current value, which
+/// is not affected by later writes. [`borrow_mut`](GcCowCell::borrow_mut)
+/// gives the writer its own clone of the value, and replaces the value
+/// with it when the guard is dropped. So unlike [`GcCell`], a
+/// `GcCowCell` can be modified while a snapshot of it is being iterated
+/// over, or while another write is in progress. If two writes overlap,
+/// the one that finishes last wins.
+///
+/// Every write allocates a new `Gc` for the new value, and snapshots
+/// that are no longer used are collected like any other object.
+///
+/// # Examples
+///
+/// ```
+/// use gc::{Gc, GcCowCell};
+///
+/// let list = GcCowCell::new(vec![1, 2, 3]);
+/// for x in list.snapshot().iter() {
+///     list.borrow_mut().push(*x * 10);
+/// }
+/// assert_eq!(*list.snapshot(), [1, 2, 3, 10, 20, 30]);
+/// ```
+pub struct GcCowCell<T: Trace + 'static> {
+    current: GcCell<Gc<T>>,
+}
+
+impl<T: Trace> GcCowCell<T> {
+    /// Creates a new `GcCowCell` containing `value`.
+    pub fn new(value: T) -> Self {
+        GcCowCell {
+            current: GcCell::new(Gc::new(value)),
+        }
+    }
+
+    /// Returns the current value. Later writes to the cell don't change
+    /// the returned value.
+    pub fn snapshot(&self) -> Gc<T> {
+        self.current.borrow().clone()
+    }
+
+    /// Replaces the value.
+    pub fn set(&self, value: T) {
+        let value = Gc::new(value);
+        // The old value is dropped after the borrow has ended, since its
+        // destructor may access this cell.
+        let old = mem::replace(&mut *self.current.borrow_mut(), value);
+        drop(old);
+    }
+}
+
+impl<T: Trace + Clone> GcCowCell<T> {
+    /// Returns a guard holding a clone of the current value, which
+    /// replaces the value of the cell when the guard is dropped.
+    ///
+    /// If the thread panics while the guard is alive, the changes are
+    /// discarded.
+    pub fn borrow_mut(&self) -> GcCowRefMut<'_, T> {
+        GcCowRefMut {
+            cell: self,
+            value: Some((*self.snapshot()).clone()),
+        }
+    }
+}
+
+impl<T: Trace + Default> Default for GcCowCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Trace + Debug> Debug for GcCowCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcCowCell")
+            .field("value", &*self.snapshot())
+            .finish()
+    }
+}
+
+impl<T: Trace> Finalize for GcCowCell<T> {}
+
+unsafe impl<T: Trace> Trace for GcCowCell<T> {
+    custom_trace!(this, mark(&this.current));
+}
+
+/// A guard returned by [`GcCowCell::borrow_mut`]. It holds the new value
+/// of the cell, which is rooted until the guard is dropped.
+pub struct GcCowRefMut<'a, T: Trace + 'static> {
+    cell: &'a GcCowCell<T>,
+    value: Option<T>,
+}
+
+impl<T: Trace> Deref for GcCowRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T: Trace> DerefMut for GcCowRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T: Trace> Drop for GcCowRefMut<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take()
+            && !std::thread::panicking()
+        {
+            self.cell.set(value);
+        }
+    }
+}
+
+impl<T: Trace + Debug> Debug for GcCowRefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
 // Sets the data pointer of a `?Sized` raw pointer.
 //
 // For a slice/trait object, this sets the `data` field and leaves the rest
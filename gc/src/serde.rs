@@ -1,6 +1,8 @@
 use crate::{Gc, Trace};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+/// Deserializes the value into a fresh allocation, so every `Gc` in the
+/// input becomes a separate, unshared `Gc`.
 impl<'de, T: Deserialize<'de> + Trace> Deserialize<'de> for Gc<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -10,6 +12,12 @@ impl<'de, T: Deserialize<'de> + Trace> Deserialize<'de> for Gc<T> {
     }
 }
 
+/// Serializes the pointed-to value as if the `Gc` were not there.
+///
+/// Sharing is not preserved: a value reachable through several `Gc`s is
+/// written out once for each of them. Serializing a cyclic graph does not
+/// terminate, so cycles have to be broken up, for example by storing ids
+/// instead of `Gc`s.
 impl<T: Serialize> Serialize for Gc<T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
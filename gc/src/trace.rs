@@ -1,10 +1,14 @@
+use crate::FinalizeContext;
 use std::borrow::{Cow, ToOwned};
+use std::cell::{Cell, RefCell};
 use std::collections::hash_map::{DefaultHasher, RandomState};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
 use std::hash::BuildHasherDefault;
 #[allow(deprecated)]
 use std::hash::SipHasher;
 use std::marker::PhantomData;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use std::num::{
     NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize, NonZeroU8,
     NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
@@ -15,35 +19,139 @@ use std::sync::atomic::{
     AtomicBool, AtomicI8, AtomicI16, AtomicI32, AtomicI64, AtomicIsize, AtomicU8, AtomicU16,
     AtomicU32, AtomicU64, AtomicUsize,
 };
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 
 /// The Finalize trait, which needs to be implemented on
 /// garbage-collected objects to define finalization logic.
 pub trait Finalize {
     fn finalize(&self) {}
+
+    /// Called instead of `finalize` when the collector finalizes the
+    /// value, with information about the collection. By default it
+    /// calls `finalize`.
+    ///
+    /// `finalize` is still called on its own when a value is dropped
+    /// outside of a collection, so cleanup that must always happen
+    /// belongs there. [`finalize_context`](crate::finalize_context)
+    /// gives the same information to code called from `finalize`.
+    fn finalize_with(&self, context: FinalizeContext) {
+        let _ = context;
+        self.finalize();
+    }
+}
+
+/// Finalizes `value` on its own, for `Trace::finalize_glue`. This calls
+/// `Finalize::finalize_with` if the collector is running finalizers, and
+/// `Finalize::finalize` otherwise.
+#[doc(hidden)]
+pub fn finalize_in_glue<T: Finalize + ?Sized>(value: &T) {
+    match crate::finalize_context() {
+        Some(context) => value.finalize_with(context),
+        None => value.finalize(),
+    }
+}
+
+mod sealed {
+    use std::any::TypeId;
+
+    /// Type information for `dyn Trace` values. It is implemented for
+    /// every type and can't be implemented elsewhere, so it can't be
+    /// overridden.
+    pub trait TypeInfo {
+        /// Returns the `TypeId` of `Self`. This is what lets
+        /// [`Gc::downcast`](crate::Gc::downcast) recover the concrete
+        /// type of a `Gc<dyn Trace>`.
+        fn gc_type_id(&self) -> TypeId
+        where
+            Self: 'static;
+
+        /// Returns the name of the type of `Self`, as given by
+        /// [`std::any::type_name`], for diagnostics.
+        fn gc_type_name(&self) -> &'static str;
+    }
+
+    impl<T: ?Sized> TypeInfo for T {
+        #[inline]
+        fn gc_type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<Self>()
+        }
+
+        #[inline]
+        fn gc_type_name(&self) -> &'static str {
+            std::any::type_name::<Self>()
+        }
+    }
 }
 
 /// The Trace trait, which needs to be implemented on garbage-collected objects.
 ///
 /// # Safety
-pub unsafe trait Trace: Finalize {
+///
+/// Implementations must visit every `Gc` that the value owns, directly
+/// or indirectly, in each of `trace`, `root` and `unroot`, and must visit
+/// the same ones in all three. A `Gc` that is missed by `trace` can be
+/// freed while it is still in use. Use `#[derive(Trace)]` or
+/// [`custom_trace!`](crate::custom_trace) rather than writing the methods by hand.
+///
+/// # Rooting
+///
+/// A `Gc` that is not stored inside a garbage-collected allocation, such
+/// as one on the stack, in a `Vec` owned by the stack, or leaked with
+/// [`Gc::into_raw`](crate::Gc::into_raw), is a root and keeps its
+/// allocation alive. No explicit rooting is needed to keep such a `Gc`
+/// alive across a collection. The collector calls `root` and `unroot`
+/// when values move out of and into the heap, and these are normally
+/// not called directly.
+pub unsafe trait Trace: Finalize + sealed::TypeInfo {
     /// Marks all contained `Gc`s.
     ///
     /// # Safety
+    ///
+    /// This must only be called by the collector while it is marking.
     unsafe fn trace(&self);
 
     /// Increments the root-count of all contained `Gc`s.
     ///
     /// # Safety
+    ///
+    /// The contained `Gc`s must currently be unrooted, which is the case
+    /// for values stored in a garbage-collected allocation. Every call
+    /// must be balanced by a call to `unroot` before the value is traced
+    /// as part of the heap again, or the allocations it refers to will
+    /// never be freed. Rooting a `Gc` twice panics.
     unsafe fn root(&self);
 
     /// Decrements the root-count of all contained `Gc`s.
     ///
     /// # Safety
+    ///
+    /// The contained `Gc`s must currently be rooted, and the value must
+    /// be reachable from a root for as long as it stays unrooted, since
+    /// the collector no longer considers its `Gc`s to be roots.
+    /// Unrooting a `Gc` twice panics.
     unsafe fn unroot(&self);
 
     /// Runs `Finalize::finalize()` on this object and all
     /// contained subobjects
     fn finalize_glue(&self);
+
+    /// Returns the number of bytes of heap memory this value owns outside
+    /// of its own `size_of_val`, such as the buffer of a `Vec`.
+    ///
+    /// It is added to `bytes_allocated` when the value is moved into a
+    /// `Gc`, so that values owning large buffers trigger collections
+    /// sooner, and is subtracted again when the `Gc` is freed. Later
+    /// changes to the value are not accounted for. `#[derive(Trace)]`
+    /// sums the traced fields, and `Vec`, `String` and `Box` report their
+    /// buffers. Other types report nothing unless they override this.
+    #[inline]
+    fn heap_size(&self) -> usize {
+        0
+    }
 }
 
 /// This rule implements the trace methods with empty implementations.
@@ -60,7 +168,7 @@ macro_rules! unsafe_empty_trace {
         unsafe fn unroot(&self) {}
         #[inline]
         fn finalize_glue(&self) {
-            $crate::Finalize::finalize(self)
+            $crate::finalize_in_glue(self)
         }
     };
 }
@@ -106,7 +214,7 @@ macro_rules! custom_trace {
             fn mark<T: $crate::Trace + ?Sized>(it: &T) {
                 $crate::Trace::finalize_glue(it);
             }
-            $crate::Finalize::finalize(self);
+            $crate::finalize_in_glue(self);
             let $this = self;
             #[allow(unused_unsafe)]
             $body
@@ -114,11 +222,32 @@ macro_rules! custom_trace {
     };
 }
 
+// A `Gc` can't be borrowed for `'static`, since it may be collected, so
+// nothing behind a `'static` reference is ever garbage-collected data.
 impl<T: ?Sized> Finalize for &'static T {}
 unsafe impl<T: ?Sized> Trace for &'static T {
     unsafe_empty_trace!();
 }
 
+// The contents of these types are opaque to the collector. Shared
+// ownership and unchecked mutation mean the `Gc`s inside them can't be
+// unrooted safely, so those `Gc`s stay roots for as long as they live
+// there, and cycles through them are never collected. Use `Gc` and
+// `GcCell` instead to share or mutate traced data. Locks are not taken
+// to trace them, so tracing can't deadlock on a lock that is held.
+macro_rules! opaque_finalize_trace {
+    ($($T:ident),*) => {
+        $(
+            impl<T: ?Sized> Finalize for $T<T> {}
+            unsafe impl<T: ?Sized> Trace for $T<T> {
+                unsafe_empty_trace!();
+            }
+        )*
+    }
+}
+
+opaque_finalize_trace![Rc, Arc, RefCell, Mutex, RwLock];
+
 macro_rules! simple_empty_finalize_trace {
     ($($T:ty),*) => {
         $(
@@ -148,9 +277,7 @@ simple_empty_finalize_trace![
     f32,
     f64,
     char,
-    String,
     str,
-    Rc<str>,
     Path,
     PathBuf,
     NonZeroIsize,
@@ -178,9 +305,24 @@ simple_empty_finalize_trace![
     AtomicU64,
     DefaultHasher,
     SipHasher,
-    RandomState
+    RandomState,
+    Duration,
+    Instant,
+    SystemTime,
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+    SocketAddrV4,
+    SocketAddrV6
 ];
 
+// A `Copy` type can't contain a `Gc`, so there is nothing to trace.
+impl<T: Copy> Finalize for Cell<T> {}
+unsafe impl<T: Copy> Trace for Cell<T> {
+    unsafe_empty_trace!();
+}
+
 impl<T, const N: usize> Finalize for [T; N] {}
 unsafe impl<T: Trace, const N: usize> Trace for [T; N] {
     custom_trace!(this, {
@@ -257,6 +399,11 @@ unsafe impl<T: Trace + ?Sized> Trace for Box<T> {
     custom_trace!(this, {
         mark(&**this);
     });
+
+    #[inline]
+    fn heap_size(&self) -> usize {
+        mem::size_of_val::<T>(&**self) + T::heap_size(&**self)
+    }
 }
 
 impl<T> Finalize for [T] {}
@@ -275,6 +422,21 @@ unsafe impl<T: Trace> Trace for Vec<T> {
             mark(e);
         }
     });
+
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl Finalize for String {}
+unsafe impl Trace for String {
+    unsafe_empty_trace!();
+
+    #[inline]
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
 }
 
 impl<T> Finalize for Option<T> {}
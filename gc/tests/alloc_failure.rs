@@ -0,0 +1,43 @@
+use gc::{AllocError, Gc, force_collect};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// Fails allocations of at least `LARGE` bytes while `FAIL_LARGE` is set
+// on the allocating thread.
+const LARGE: usize = 1 << 16;
+
+thread_local!(static FAIL_LARGE: Cell<bool> = const { Cell::new(false) });
+
+struct FailingAllocator;
+
+unsafe impl GlobalAlloc for FailingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= LARGE && FAIL_LARGE.try_with(Cell::get).unwrap_or(false) {
+            return std::ptr::null_mut();
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: FailingAllocator = FailingAllocator;
+
+#[test]
+fn try_new() {
+    force_collect();
+    let small = Gc::try_new(1).unwrap();
+
+    FAIL_LARGE.set(true);
+    let large = Gc::try_new([0u8; LARGE]);
+    FAIL_LARGE.set(false);
+    assert_eq!(large.err(), Some(AllocError));
+
+    // Failing to allocate leaves the collector usable.
+    let large = Gc::try_new([1u8; LARGE]).unwrap();
+    force_collect();
+    assert_eq!((*small, large[0]), (1, 1));
+}
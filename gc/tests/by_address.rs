@@ -0,0 +1,50 @@
+use gc::{Finalize, Gc, GcByAddress, GcCell, Trace, force_collect};
+use std::collections::HashMap;
+
+#[derive(Trace, Finalize)]
+struct Node {
+    edges: GcCell<Vec<Gc<Node>>>,
+}
+
+#[test]
+fn identity_keys() {
+    let a = Gc::new(Node {
+        edges: GcCell::new(Vec::new()),
+    });
+    let b = Gc::new(Node {
+        edges: GcCell::new(vec![a.clone()]),
+    });
+    a.edges.borrow_mut().push(b.clone());
+
+    // The map lives on the heap, so its keys have to be traced.
+    let weights = Gc::new(GcCell::new(HashMap::new()));
+    weights.borrow_mut().insert(GcByAddress(a.clone()), 1);
+    weights.borrow_mut().insert(GcByAddress(b.clone()), 2);
+    drop(b);
+    force_collect();
+
+    let weights = weights.borrow();
+    assert_eq!(weights[&GcByAddress(a.clone())], 1);
+    let b = a.edges.borrow()[0].clone();
+    assert_eq!(weights[&GcByAddress(b)], 2);
+}
+
+#[test]
+// The root bit in `Gc` is interior mutability, but it doesn't affect the
+// address.
+#[allow(clippy::mutable_key_type)]
+fn ordered_by_address() {
+    use std::collections::BTreeSet;
+
+    let values: Vec<_> = (0..10).map(|_| Gc::new(0)).collect();
+    let set: BTreeSet<_> = values.iter().cloned().map(GcByAddress).collect();
+    assert_eq!(set.len(), 10);
+
+    // The order follows the addresses, and stays the same across
+    // collections.
+    let order: Vec<_> = set.iter().map(|gc| Gc::as_ptr(gc)).collect();
+    assert!(order.is_sorted());
+    force_collect();
+    assert!(set.iter().map(|gc| Gc::as_ptr(gc)).eq(order));
+    assert!(set.contains(&GcByAddress(values[3].clone())));
+}
@@ -0,0 +1,19 @@
+use gc::{Gc, force_collect};
+
+#[test]
+fn clone_from() {
+    let a = Gc::new(1);
+    let b = Gc::new(2);
+    let mut x = a.clone();
+
+    x.clone_from(&a);
+    assert!(Gc::ptr_eq(&x, &a));
+
+    x.clone_from(&b);
+    assert!(Gc::ptr_eq(&x, &b));
+
+    // `a` and `x` keep their allocations alive independently.
+    drop(b);
+    force_collect();
+    assert_eq!((*a, *x), (1, 2));
+}
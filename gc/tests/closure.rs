@@ -0,0 +1,41 @@
+use gc::{Finalize, Gc, GcCell, GcClosure, Trace, force_collect};
+use std::cell::Cell;
+
+thread_local!(static DROPPED: Cell<bool> = const { Cell::new(false) });
+
+struct DropFlag;
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        DROPPED.set(true);
+    }
+}
+
+#[derive(Trace, Finalize)]
+struct Button {
+    clicks: GcCell<u32>,
+    on_click: GcCell<Option<GcClosure<Gc<Button>, u32>>>,
+    #[unsafe_ignore_trace]
+    _flag: DropFlag,
+}
+
+#[test]
+fn handler_cycle() {
+    let button = Gc::new(Button {
+        clicks: GcCell::new(0),
+        on_click: GcCell::new(None),
+        _flag: DropFlag,
+    });
+    *button.on_click.borrow_mut() = Some(GcClosure::new(button.clone(), |button, n| {
+        *button.clicks.borrow_mut() += n;
+    }));
+
+    force_collect();
+    button.on_click.borrow().as_ref().unwrap().call(2);
+    assert_eq!(*button.clicks.borrow(), 2);
+
+    // The handler refers back to the button, but the cycle is traced.
+    drop(button);
+    force_collect();
+    assert!(DROPPED.get());
+}
@@ -0,0 +1,51 @@
+use gc::{Finalize, Gc, GcCell, Trace, collect_if_unreferenced, force_collect};
+use std::cell::Cell;
+
+thread_local!(static DROPPED: Cell<usize> = const { Cell::new(0) });
+
+struct Flag;
+
+impl Drop for Flag {
+    fn drop(&mut self) {
+        DROPPED.set(DROPPED.get() + 1);
+    }
+}
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+    #[unsafe_ignore_trace]
+    _flag: Flag,
+}
+
+fn node(next: Option<Gc<Node>>) -> Gc<Node> {
+    Gc::new(Node {
+        next: GcCell::new(next),
+        _flag: Flag,
+    })
+}
+
+#[test]
+fn frees_unreferenced_box() {
+    force_collect();
+    DROPPED.set(0);
+
+    let child = node(None);
+    let parent = node(Some(child.clone()));
+    let child = collect_if_unreferenced(child).unwrap_err();
+    assert_eq!(DROPPED.get(), 0);
+
+    assert!(collect_if_unreferenced(parent).is_ok());
+    assert_eq!(DROPPED.get(), 1);
+    assert!(collect_if_unreferenced(child).is_ok());
+    assert_eq!(DROPPED.get(), 2);
+}
+
+#[test]
+fn cycle_is_referenced() {
+    let a = node(None);
+    *a.next.borrow_mut() = Some(a.clone());
+    let a = collect_if_unreferenced(a).unwrap_err();
+    *a.next.borrow_mut() = None;
+    assert!(collect_if_unreferenced(a).is_ok());
+}
@@ -0,0 +1,47 @@
+use gc::{Finalize, Gc, Trace, collect_on_drop, disable, enable, force_collect};
+use std::cell::Cell;
+
+thread_local!(static DROPPED: Cell<usize> = const { Cell::new(0) });
+
+struct Flag;
+
+impl Drop for Flag {
+    fn drop(&mut self) {
+        DROPPED.set(DROPPED.get() + 1);
+    }
+}
+
+#[derive(Trace, Finalize)]
+struct Counted(#[unsafe_ignore_trace] Flag);
+
+#[derive(Trace, Finalize)]
+struct Holder(#[unsafe_ignore_trace] gc::CollectOnDrop);
+
+#[test]
+fn collects_on_drop() {
+    force_collect();
+    DROPPED.set(0);
+
+    {
+        let _guard = collect_on_drop();
+        drop(Gc::new(Counted(Flag)));
+        assert_eq!(DROPPED.get(), 0);
+    }
+    assert_eq!(DROPPED.get(), 1);
+
+    disable();
+    {
+        let _guard = collect_on_drop();
+        drop(Gc::new(Counted(Flag)));
+    }
+    enable();
+    assert_eq!(DROPPED.get(), 1);
+    force_collect();
+    assert_eq!(DROPPED.get(), 2);
+}
+
+#[test]
+fn dropped_while_collecting() {
+    drop(Gc::new(Holder(collect_on_drop())));
+    force_collect();
+}
@@ -0,0 +1,41 @@
+use gc::{CollectionOutcome, Finalize, Gc, GcCell, Trace, collect_with_budget, force_collect};
+use std::time::Duration;
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+fn node() -> Gc<Node> {
+    Gc::new(Node {
+        next: GcCell::new(None),
+    })
+}
+
+#[test]
+fn completes_within_budget() {
+    force_collect();
+    drop(node());
+    let CollectionOutcome::Completed(report) = collect_with_budget(Duration::from_secs(60)) else {
+        panic!("collection ran out of time");
+    };
+    assert_eq!(report.objects_freed, 1);
+}
+
+#[test]
+fn abandoned_when_over_budget() {
+    force_collect();
+    let kept: Vec<_> = (0..100).map(|_| node()).collect();
+    let a = node();
+    *a.next.borrow_mut() = Some(node());
+    drop(a);
+    assert_eq!(
+        collect_with_budget(Duration::ZERO),
+        CollectionOutcome::Budgeted
+    );
+
+    // Nothing was freed, and the marks were cleared, so a later
+    // collection still finds the garbage.
+    assert!(kept.iter().all(|n| n.next.borrow().is_none()));
+    assert_eq!(force_collect().objects_freed, 2);
+}
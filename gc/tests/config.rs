@@ -0,0 +1,391 @@
+#![cfg(all(feature = "unstable-config", feature = "unstable-stats"))]
+
+use gc::{Gc, GcStats, configure, force_collect};
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn on_collect() {
+    let calls = Rc::new(Cell::new(0));
+    let hook_calls = calls.clone();
+    configure(|config| {
+        config.on_collect = Some(Rc::new(move |stats: &GcStats| {
+            hook_calls.set(hook_calls.get() + 1);
+            assert!(stats.collections_performed > 0);
+            // The collector is not borrowed while the hook runs.
+            let _ = Gc::new(stats.collections_performed);
+        }));
+    });
+
+    force_collect();
+    assert_eq!(calls.get(), 1);
+
+    configure(|config| config.threshold = 0);
+    let _a = Gc::new(1);
+    let _b = Gc::new(2);
+    assert!(calls.get() >= 2);
+
+    configure(|config| config.on_collect = None);
+    let before = calls.get();
+    force_collect();
+    assert_eq!(calls.get(), before);
+}
+
+#[test]
+fn min_collect_interval() {
+    use std::time::Duration;
+
+    configure(|config| {
+        config.threshold = 0;
+        config.min_collect_interval = Some(Duration::from_secs(3600));
+    });
+    force_collect();
+    let before = gc::stats().collections_performed;
+    let _values: Vec<_> = (0..100).map(Gc::new).collect();
+    assert_eq!(gc::stats().collections_performed, before);
+
+    // Forcing a collection ignores the interval.
+    force_collect();
+    assert_eq!(gc::stats().collections_performed, before + 1);
+
+    configure(|config| config.min_collect_interval = None);
+    let _value = Gc::new(0);
+    assert_eq!(gc::stats().collections_performed, before + 2);
+}
+
+#[test]
+fn sweep_budget() {
+    use gc::{Finalize, Trace};
+
+    thread_local! {
+        static FINALIZED: Cell<usize> = const { Cell::new(0) };
+        static DROPPED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPPED.with(|d| d.set(d.get() + 1));
+        }
+    }
+
+    #[derive(Trace)]
+    struct Counted(#[unsafe_ignore_trace] DropCounter);
+
+    impl Finalize for Counted {
+        fn finalize(&self) {
+            FINALIZED.with(|f| f.set(f.get() + 1));
+        }
+    }
+
+    configure(|config| config.sweep_budget = Some(2));
+    gc::disable();
+    for _ in 0..10 {
+        drop(Gc::new(Counted(DropCounter)));
+    }
+    configure(|config| config.threshold = 0);
+    gc::enable();
+
+    // The collection finalizes everything, but only drops two objects.
+    let _a = Gc::new(0u8);
+    assert_eq!(FINALIZED.with(Cell::get), 10);
+    assert_eq!(DROPPED.with(Cell::get), 2);
+    assert_eq!(gc::stats().live_objects, 1);
+
+    gc::disable();
+    let _b = Gc::new(1u8);
+    assert_eq!(DROPPED.with(Cell::get), 4);
+
+    // Forcing a collection drops the rest.
+    force_collect();
+    assert_eq!(FINALIZED.with(Cell::get), 10);
+    assert_eq!(DROPPED.with(Cell::get), 10);
+}
+
+#[test]
+fn run_finalizers() {
+    use gc::{Finalize, Trace};
+
+    thread_local!(static FINALIZED: Cell<usize> = const { Cell::new(0) });
+
+    #[derive(Trace)]
+    struct Counted;
+
+    impl Finalize for Counted {
+        fn finalize(&self) {
+            FINALIZED.with(|f| f.set(f.get() + 1));
+        }
+    }
+
+    configure(|config| config.run_finalizers = false);
+    drop(Gc::new(Counted));
+    let report = force_collect();
+    assert_eq!(report.objects_freed, 1);
+    assert_eq!(report.objects_finalized, 0);
+    assert_eq!(FINALIZED.with(Cell::get), 0);
+
+    configure(|config| config.run_finalizers = true);
+    drop(Gc::new(Counted));
+    force_collect();
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+}
+
+#[test]
+fn memory_limit() {
+    configure(|config| {
+        config.threshold = usize::MAX;
+        config.min_collect_interval = Some(std::time::Duration::from_secs(3600));
+    });
+    force_collect();
+    let limit = gc::stats().bytes_allocated + 1000;
+    configure(|config| config.memory_limit = Some(limit));
+
+    // Garbage is collected to stay under the limit, even though neither
+    // the threshold nor the interval would allow a collection.
+    for i in 0..1000 {
+        drop(Gc::new(i));
+    }
+    assert!(gc::stats().bytes_allocated <= limit);
+
+    // Reachable objects can't be collected, so allocation fails.
+    let result = std::panic::catch_unwind(|| {
+        let mut values = Vec::new();
+        loop {
+            values.push(Gc::new(0u64));
+        }
+    });
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("memory limit"), "{message}");
+
+    configure(|config| config.memory_limit = None);
+    force_collect();
+    let _value = Gc::new(0);
+}
+
+#[test]
+fn try_new_over_memory_limit() {
+    configure(|config| {
+        config.threshold = usize::MAX;
+        config.min_collect_interval = Some(std::time::Duration::from_secs(3600));
+    });
+    force_collect();
+    let limit = gc::stats().bytes_allocated + 1000;
+    configure(|config| config.memory_limit = Some(limit));
+
+    let mut values = Vec::new();
+    let error = loop {
+        match Gc::try_new(0u64) {
+            Ok(value) => values.push(value),
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error, gc::AllocError);
+    assert!(gc::stats().bytes_allocated <= limit);
+
+    drop(values);
+    assert!(Gc::try_new(0u64).is_ok());
+
+    configure(|config| config.memory_limit = None);
+}
+
+#[test]
+fn finalize_order() {
+    use gc::{Finalize, FinalizeOrder, Trace};
+    use std::cell::RefCell;
+
+    thread_local!(static FINALIZED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) });
+
+    #[derive(Trace)]
+    struct Node {
+        id: u8,
+        children: Vec<Gc<Node>>,
+    }
+
+    impl Finalize for Node {
+        fn finalize(&self) {
+            FINALIZED.with(|f| f.borrow_mut().push(self.id));
+        }
+    }
+
+    fn finalized_tree(order: FinalizeOrder) -> Vec<u8> {
+        configure(|config| config.finalize_order = order);
+        // Parents are allocated after their children.
+        let leaf = Gc::new(Node {
+            id: 3,
+            children: Vec::new(),
+        });
+        let child = Gc::new(Node {
+            id: 2,
+            children: vec![leaf.clone()],
+        });
+        drop(Gc::new(Node {
+            id: 1,
+            children: vec![child, leaf],
+        }));
+        force_collect();
+        FINALIZED.with(|f| f.take())
+    }
+
+    force_collect();
+    assert_eq!(finalized_tree(FinalizeOrder::NewestFirst), [1, 2, 3]);
+    assert_eq!(finalized_tree(FinalizeOrder::ChildrenFirst), [3, 2, 1]);
+}
+
+#[test]
+fn on_pre_collect() {
+    use std::cell::RefCell;
+
+    force_collect();
+    let cache = Rc::new(RefCell::new(Some(Gc::new(1))));
+    let hook_cache = cache.clone();
+    configure(|config| {
+        config.on_pre_collect = Some(Rc::new(move |_: &GcStats| {
+            // The hook may allocate without running itself again.
+            let _ = Gc::new(2);
+            hook_cache.borrow_mut().take();
+        }));
+    });
+
+    // The evicted entry is freed by the collection that evicted it,
+    // along with the hook's own allocation.
+    assert_eq!(force_collect().objects_freed, 2);
+    assert!(cache.borrow().is_none());
+    configure(|config| config.on_pre_collect = None);
+}
+
+#[test]
+fn on_pre_collect_is_followed_by_a_collection() {
+    let _first = Gc::new(0);
+    configure(|config| {
+        config.threshold = 0;
+        // Raising the threshold doesn't cancel the collection the hook
+        // was called for.
+        config.on_pre_collect = Some(Rc::new(|_: &GcStats| {
+            configure(|config| config.threshold = usize::MAX);
+        }));
+    });
+    let before = gc::stats().collections_performed;
+    let _second = Gc::new(0);
+    assert_eq!(gc::stats().collections_performed, before + 1);
+    configure(|config| config.on_pre_collect = None);
+}
+
+#[test]
+fn snapshot_and_restore() {
+    use gc::{GcConfig, config_snapshot, set_config};
+
+    configure(|config| config.threshold = 12345);
+    let saved = config_snapshot();
+
+    set_config(GcConfig {
+        threshold: 0,
+        ..GcConfig::default()
+    });
+    let _first = Gc::new(0);
+    let before = gc::stats().collections_performed;
+    let _second = Gc::new(0);
+    assert_eq!(gc::stats().collections_performed, before + 1);
+
+    set_config(saved);
+    assert_eq!(config_snapshot().threshold, 12345);
+}
+
+#[test]
+fn collect_every_n_allocations() {
+    configure(|config| {
+        config.threshold = usize::MAX;
+        config.collect_every_n_allocations = Some(10);
+    });
+    force_collect();
+    let before = gc::stats().collections_performed;
+
+    // The 11th and 21st allocations each find 10 allocations since the
+    // previous collection.
+    let _values: Vec<_> = (0..30).map(Gc::new).collect();
+    assert_eq!(gc::stats().collections_performed, before + 2);
+    let _value = Gc::new(0);
+    assert_eq!(gc::stats().collections_performed, before + 3);
+
+    configure(|config| config.collect_every_n_allocations = None);
+    let _values: Vec<_> = (0..30).map(Gc::new).collect();
+    assert_eq!(gc::stats().collections_performed, before + 3);
+}
+
+#[test]
+fn skip_finalizers_on_drop() {
+    use gc::{Finalize, Trace};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct DropCounter;
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Trace)]
+    struct Counted(#[unsafe_ignore_trace] DropCounter);
+
+    impl Finalize for Counted {
+        fn finalize(&self) {
+            FINALIZED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    std::thread::spawn(|| {
+        configure(|config| config.skip_finalizers_on_drop = true);
+        let _kept = Gc::new(Counted(DropCounter));
+        drop(Gc::new(Counted(DropCounter)));
+    })
+    .join()
+    .unwrap();
+    assert_eq!(FINALIZED.load(Ordering::SeqCst), 0);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn finalize_order_at_thread_exit() {
+    use gc::{Finalize, FinalizeOrder, GcCell, Trace};
+    use std::sync::Mutex;
+
+    static FINALIZED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+    #[derive(Trace)]
+    struct Node {
+        id: u8,
+        next: GcCell<Option<Gc<Node>>>,
+    }
+
+    impl Finalize for Node {
+        fn finalize(&self) {
+            FINALIZED.lock().unwrap().push(self.id);
+        }
+    }
+
+    std::thread::spawn(|| {
+        configure(|config| config.finalize_order = FinalizeOrder::ChildrenFirst);
+        // Nothing is collected before the thread exits.
+        gc::disable();
+        for id in 0..3 {
+            let a = Gc::new(Node {
+                id: 2 * id,
+                next: GcCell::new(None),
+            });
+            let b = Gc::new(Node {
+                id: 2 * id + 1,
+                next: GcCell::new(Some(a.clone())),
+            });
+            *a.next.borrow_mut() = Some(b);
+        }
+    })
+    .join()
+    .unwrap();
+
+    // The cycles are finalized newest first when the thread exits.
+    assert_eq!(*FINALIZED.lock().unwrap(), [5, 4, 3, 2, 1, 0]);
+}
@@ -0,0 +1,57 @@
+use gc::{Finalize, Gc, GcCowCell, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    children: GcCowCell<Vec<Gc<Node>>>,
+    data: u8,
+}
+
+fn node(data: u8) -> Gc<Node> {
+    Gc::new(Node {
+        children: GcCowCell::default(),
+        data,
+    })
+}
+
+#[test]
+fn snapshots_are_unaffected_by_writes() {
+    let cell = GcCowCell::new(vec![1]);
+    let before = cell.snapshot();
+    {
+        let mut guard = cell.borrow_mut();
+        guard.push(2);
+        assert_eq!(*cell.snapshot(), [1]);
+    }
+    assert_eq!(*before, [1]);
+    assert_eq!(*cell.snapshot(), [1, 2]);
+
+    cell.set(vec![3]);
+    assert_eq!(*cell.snapshot(), [3]);
+}
+
+#[test]
+fn mutate_while_iterating() {
+    let root = node(0);
+    root.children.set(vec![node(1), node(2)]);
+    for child in root.children.snapshot().iter() {
+        let mut children = root.children.borrow_mut();
+        children.push(node(child.data + 10));
+        children.push(root.clone());
+    }
+    force_collect();
+
+    let data: Vec<_> = root.children.snapshot().iter().map(|n| n.data).collect();
+    assert_eq!(data, [1, 2, 11, 0, 12, 0]);
+}
+
+#[test]
+fn panicking_write_is_discarded() {
+    let cell = GcCowCell::new(vec![1]);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.borrow_mut().push(2);
+        let _guard = cell.borrow_mut();
+        panic!("discard");
+    }));
+    assert!(result.is_err());
+    assert_eq!(*cell.snapshot(), [1, 2]);
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "unstable-debug")]
+
+use gc::debug;
+use gc::{Finalize, Gc, GcCell, Trace, force_collect};
+use std::any::TypeId;
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+fn count_nodes() -> usize {
+    let mut count = 0;
+    debug::for_each_live(|value| {
+        if value.gc_type_id() == TypeId::of::<Node>() {
+            count += 1;
+        }
+    });
+    count
+}
+
+#[test]
+fn for_each_live() {
+    force_collect();
+    let size = debug::heap_size();
+
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    *a.next.borrow_mut() = Some(b);
+    assert_eq!(count_nodes(), 2);
+    assert!(debug::heap_size() > size);
+
+    // Unreachable objects are listed until they are collected.
+    drop(a);
+    assert_eq!(count_nodes(), 2);
+    force_collect();
+    assert_eq!(count_nodes(), 0);
+    assert_eq!(debug::heap_size(), size);
+}
+
+#[test]
+fn find_cycles() {
+    force_collect();
+    let ptr = |node: &Gc<Node>| Gc::as_ptr(node).cast::<()>();
+
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    let c = Gc::new(Node {
+        next: GcCell::new(Some(b.clone())),
+    });
+    assert!(debug::find_cycles().is_empty());
+
+    *a.next.borrow_mut() = Some(b.clone());
+    *c.next.borrow_mut() = Some(c.clone());
+    // Both cycles are directly rooted.
+    assert!(debug::find_cycles().is_empty());
+
+    let mut expected = vec![ptr(&a), ptr(&b)];
+    expected.sort();
+    drop((a, b));
+    let mut cycles = debug::find_cycles();
+    assert_eq!(cycles.len(), 1);
+    cycles[0].sort();
+    assert_eq!(cycles[0], expected);
+
+    drop(c);
+    assert_eq!(debug::find_cycles().len(), 2);
+    force_collect();
+    assert!(debug::find_cycles().is_empty());
+}
+
+#[test]
+fn stats_by_type() {
+    force_collect();
+    let name = std::any::type_name::<Node>();
+    assert!(!debug::stats_by_type().contains_key(name));
+
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let mut b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    let (count, bytes) = debug::stats_by_type()[name];
+    assert_eq!(count, 2);
+    assert_eq!(bytes % 2, 0);
+    let total: usize = debug::stats_by_type()
+        .values()
+        .map(|&(_, bytes)| bytes)
+        .sum();
+    assert_eq!(total, debug::heap_size());
+
+    // Borrowed objects are still counted.
+    drop(a);
+    let _borrow = Gc::get_mut(&mut b).unwrap();
+    assert_eq!(debug::stats_by_type()[name], (2, bytes));
+}
+
+#[test]
+fn stats_by_type_counts_heap_size() {
+    force_collect();
+    let _values = (
+        Gc::new(vec![0u8; 1000]),
+        Gc::new(String::from("payload")),
+        Gc::new(Node {
+            next: GcCell::new(None),
+        }),
+    );
+    let stats = debug::stats_by_type();
+    let (_, bytes) = stats[std::any::type_name::<Vec<u8>>()];
+    assert!(bytes >= 1000, "{bytes}");
+    let total: usize = stats.values().map(|&(_, bytes)| bytes).sum();
+    assert_eq!(total, debug::heap_size());
+}
+
+#[test]
+fn roots_to() {
+    let ptr = |node: &Gc<Node>| Gc::as_ptr(node).cast::<()>();
+
+    let c = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(c.clone())),
+    });
+    let a = Gc::new(Node {
+        next: GcCell::new(Some(b.clone())),
+    });
+    // A cycle back to `a` doesn't keep anything alive on its own.
+    *c.next.borrow_mut() = Some(a.clone());
+    let target = ptr(&c);
+    let path = vec![ptr(&a), ptr(&b), target];
+    drop((b, c));
+    assert_eq!(debug::roots_to(target), Some(path));
+
+    drop(a);
+    assert_eq!(debug::roots_to(target), None);
+    assert_eq!(debug::roots_to(std::ptr::null()), None);
+    force_collect();
+}
+
+#[test]
+fn mark_dry_run() {
+    force_collect();
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    *a.next.borrow_mut() = Some(b.clone());
+    let c = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    drop((a, b));
+    let report = debug::mark_dry_run();
+    assert_eq!((report.reachable, report.unreachable), (3, 0));
+
+    drop(c);
+    let report = debug::mark_dry_run();
+    assert_eq!((report.reachable, report.unreachable), (0, 3));
+
+    // Nothing was freed, and the marks were cleared.
+    assert_eq!(count_nodes(), 3);
+    assert_eq!(debug::mark_dry_run(), report);
+    force_collect();
+    assert_eq!(count_nodes(), 0);
+}
+
+#[test]
+fn root_set() {
+    let ptr = |node: &Gc<Node>| Gc::as_ptr(node).cast::<()>();
+
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    let (pa, pb) = (ptr(&a), ptr(&b));
+    let roots = debug::root_set();
+    assert!(roots.contains(&pa) && roots.contains(&pb));
+
+    drop(a);
+    let roots = debug::root_set();
+    assert!(!roots.contains(&pa) && roots.contains(&pb));
+
+    drop(b);
+    assert!(!debug::root_set().contains(&pb));
+}
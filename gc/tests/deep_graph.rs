@@ -0,0 +1,61 @@
+use gc::{Finalize, Gc, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: Option<Gc<Node>>,
+}
+
+const LEN: usize = 100_000;
+
+// Far less than marking `LEN` nodes recursively would take, so these
+// tests only pass if marking doesn't recurse once per node.
+const STACK_SIZE: usize = 1 << 20;
+
+fn with_small_stack(f: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn linked_list(len: usize) -> Option<Gc<Node>> {
+    let mut head = None;
+    for _ in 0..len {
+        head = Some(Gc::new(Node { next: head }));
+    }
+    head
+}
+
+#[test]
+fn mark_long_list() {
+    with_small_stack(mark_long_list_inner);
+}
+
+fn mark_long_list_inner() {
+    let list = linked_list(LEN);
+    force_collect();
+
+    let mut len = 0;
+    let mut node = list.as_ref();
+    while let Some(n) = node {
+        len += 1;
+        node = n.next.as_ref();
+    }
+    assert_eq!(len, LEN);
+
+    drop(list);
+    assert_eq!(force_collect().objects_freed, LEN);
+}
+
+#[test]
+fn try_unwrap_long_list() {
+    with_small_stack(try_unwrap_long_list_inner);
+}
+
+fn try_unwrap_long_list_inner() {
+    let list = linked_list(LEN).unwrap();
+    let head = Gc::try_unwrap(list).unwrap_or_else(|_| panic!("head is unique"));
+    assert!(head.next.is_some());
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "unstable-config")]
+
+use gc::{Finalize, Gc, GcCell, Trace, collect_if_unreferenced, configure, force_collect};
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static FINALIZED: Cell<usize> = const { Cell::new(0) };
+    static RESURRECTED: RefCell<Option<Gc<Resource>>> = const { RefCell::new(None) };
+    static UNWRAPPED: Cell<Option<bool>> = const { Cell::new(None) };
+    static COLLECTED: Cell<Option<bool>> = const { Cell::new(None) };
+    static BORROWED_MUT: Cell<Option<(bool, bool)>> = const { Cell::new(None) };
+}
+
+#[derive(Trace, Clone)]
+struct Resource {
+    name: &'static str,
+}
+
+impl Finalize for Resource {
+    fn finalize(&self) {
+        FINALIZED.with(|f| f.set(f.get() + 1));
+        // Allocating from a finalizer panics unless finalizers are deferred.
+        let _log = Gc::new(self.name);
+    }
+}
+
+#[derive(Trace)]
+struct Owner {
+    resource: GcCell<Option<Gc<Resource>>>,
+}
+
+impl Finalize for Owner {
+    fn finalize(&self) {
+        let resource = self.resource.borrow_mut().take();
+        RESURRECTED.with(|r| *r.borrow_mut() = resource);
+    }
+}
+
+#[test]
+fn finalizers_can_allocate() {
+    configure(|config| config.defer_finalizers = true);
+    drop(Gc::new(Resource { name: "a" }));
+    let report = force_collect();
+    assert_eq!(report.objects_finalized, 1);
+    assert_eq!(report.objects_freed, 0);
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+
+    // The next collection frees it without finalizing it again. The
+    // value its finalizer allocated is queued for finalization in turn.
+    let report = force_collect();
+    assert_eq!(report.objects_freed, 1);
+    assert_eq!(report.objects_finalized, 1);
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+}
+
+#[test]
+fn finalizers_can_resurrect() {
+    configure(|config| config.defer_finalizers = true);
+    drop(Gc::new(Owner {
+        resource: GcCell::new(Some(Gc::new(Resource { name: "b" }))),
+    }));
+    force_collect();
+
+    let resource = RESURRECTED.with(|r| r.borrow_mut().take()).unwrap();
+    force_collect();
+    force_collect();
+    assert_eq!(resource.name, "b");
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+
+    drop(resource);
+    force_collect();
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+}
+
+#[derive(Trace)]
+struct Unwrapper {
+    resource: GcCell<Option<Gc<Resource>>>,
+}
+
+impl Finalize for Unwrapper {
+    fn finalize(&self) {
+        let resource = self.resource.borrow_mut().take().unwrap();
+        let unwrapped = Gc::try_unwrap(resource).is_ok();
+        UNWRAPPED.with(|u| u.set(Some(unwrapped)));
+    }
+}
+
+#[test]
+fn queued_objects_are_not_unique() {
+    configure(|config| config.defer_finalizers = true);
+    drop(Gc::new(Unwrapper {
+        resource: GcCell::new(Some(Gc::new(Resource { name: "c" }))),
+    }));
+    // The unwrapper is newer, so its finalizer runs while the resource
+    // is still queued. Unwrapping the resource would free it before its
+    // own finalizer runs.
+    force_collect();
+    assert_eq!(UNWRAPPED.with(Cell::get), Some(false));
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+}
+
+#[derive(Trace)]
+struct Collector {
+    resource: GcCell<Option<Gc<Resource>>>,
+}
+
+impl Finalize for Collector {
+    fn finalize(&self) {
+        let resource = self.resource.borrow_mut().take().unwrap();
+        let result = collect_if_unreferenced(resource);
+        COLLECTED.with(|c| c.set(Some(result.is_ok())));
+        // Hand the resource out, so it outlives the collection.
+        let resource = result.err();
+        RESURRECTED.with(|r| *r.borrow_mut() = resource);
+    }
+}
+
+#[test]
+fn queued_objects_are_not_collected_early() {
+    configure(|config| config.defer_finalizers = true);
+    drop(Gc::new(Collector {
+        resource: GcCell::new(Some(Gc::new(Resource { name: "d" }))),
+    }));
+    force_collect();
+    assert_eq!(COLLECTED.with(Cell::get), Some(false));
+    assert_eq!(FINALIZED.with(Cell::get), 1);
+
+    let resource = RESURRECTED.with(|r| r.borrow_mut().take()).unwrap();
+    assert_eq!(resource.name, "d");
+}
+
+#[derive(Trace)]
+struct Mutator {
+    resource: GcCell<Option<Gc<Resource>>>,
+}
+
+impl Finalize for Mutator {
+    fn finalize(&self) {
+        let mut resource = self.resource.borrow_mut().take().unwrap();
+        let get_mut = Gc::get_mut(&mut resource).is_some();
+        let original: *const Resource = &*resource;
+        Gc::make_mut(&mut resource).name = "f";
+        let copied = !std::ptr::eq(&*resource, original);
+        BORROWED_MUT.with(|b| b.set(Some((get_mut, copied))));
+    }
+}
+
+#[test]
+fn queued_objects_are_not_borrowed_mutably() {
+    configure(|config| config.defer_finalizers = true);
+    drop(Gc::new(Mutator {
+        resource: GcCell::new(Some(Gc::new(Resource { name: "e" }))),
+    }));
+    force_collect();
+    // `get_mut` fails and `make_mut` copies the value, since the
+    // resource's finalizer still has to run on the original.
+    assert_eq!(BORROWED_MUT.with(Cell::get), Some((false, true)));
+}
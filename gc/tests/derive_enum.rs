@@ -0,0 +1,44 @@
+use gc::{Finalize, Gc, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+enum Expr {
+    Lit(i64),
+    Add(Gc<Expr>, Gc<Expr>),
+    Call {
+        callee: Gc<Expr>,
+        args: Vec<Gc<Expr>>,
+    },
+    Nil,
+}
+
+fn eval(expr: &Expr) -> i64 {
+    match expr {
+        Expr::Lit(n) => *n,
+        Expr::Add(a, b) => eval(a) + eval(b),
+        Expr::Call { callee, args } => eval(callee) * args.iter().map(|a| eval(a)).sum::<i64>(),
+        Expr::Nil => 0,
+    }
+}
+
+#[test]
+fn enum_variants() {
+    let one = Gc::new(Expr::Lit(1));
+    let two = Gc::new(Expr::Lit(2));
+    let nil = Gc::new(Expr::Nil);
+    let sum = Gc::new(Expr::Add(one.clone(), two.clone()));
+    let call = Gc::new(Expr::Call {
+        callee: sum.clone(),
+        args: vec![two.clone(), nil.clone(), one.clone()],
+    });
+
+    // Fields of every variant are unrooted once they are on the heap.
+    assert_eq!(Gc::root_count(&one), 1);
+    assert_eq!(Gc::root_count(&two), 1);
+    assert_eq!(Gc::root_count(&sum), 1);
+    assert_eq!(Gc::root_count(&nil), 1);
+
+    // ...and traced, so they survive a collection.
+    drop((one, two, sum, nil));
+    force_collect();
+    assert_eq!(eval(&call), 9);
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "nightly")]
+
+use gc::{Finalize, Gc, GcCell, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<dyn Trace>>>,
+    data: u32,
+}
+
+#[test]
+fn downcast() {
+    let value: Gc<dyn Trace> = Gc::new(String::from("hello"));
+    let value = value.downcast::<u32>().unwrap_err();
+    let value = value
+        .downcast::<String>()
+        .unwrap_or_else(|_| panic!("a String"));
+    assert_eq!(*value, "hello");
+    assert_eq!(Gc::root_count(&value), 1);
+}
+
+#[test]
+fn downcast_keeps_graph_alive() {
+    let tail: Gc<dyn Trace> = Gc::new(7u32);
+    let head: Gc<dyn Trace> = Gc::new(Node {
+        next: GcCell::new(Some(tail)),
+        data: 1,
+    });
+    let head = head.downcast::<Node>().unwrap_or_else(|_| panic!("a Node"));
+    force_collect();
+    assert_eq!(head.data, 1);
+
+    let tail = head.next.borrow_mut().take().unwrap();
+    assert_eq!(
+        *tail.downcast::<u32>().unwrap_or_else(|_| panic!("a u32")),
+        7
+    );
+}
+
+#[test]
+fn downcast_ref_and_mut() {
+    let mut value: Gc<dyn Trace> = Gc::new(Node {
+        next: GcCell::new(None),
+        data: 1,
+    });
+    assert!(value.downcast_ref::<u32>().is_none());
+    assert_eq!(value.downcast_ref::<Node>().unwrap().data, 1);
+
+    {
+        let mut borrowed = Gc::get_mut(&mut value).unwrap();
+        assert!(borrowed.downcast_mut::<u32>().is_none());
+        borrowed.downcast_mut::<Node>().unwrap().data = 2;
+    }
+    force_collect();
+    assert_eq!(value.downcast_ref::<Node>().unwrap().data, 2);
+    assert_eq!(Gc::root_count(&value), 1);
+}
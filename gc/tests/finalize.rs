@@ -1,5 +1,5 @@
-use gc::{Finalize, Trace};
-use std::cell::Cell;
+use gc::{Finalize, Gc, GcCell, Trace, collect_until_stable, force_collect};
+use std::cell::{Cell, RefCell};
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 struct Flags(i32, i32);
@@ -46,3 +46,112 @@ fn drop_triggers_finalize() {
     }
     FLAGS.with(|f| assert_eq!(f.get(), Flags(1, 1)));
 }
+
+thread_local!(static RESURRECTED: RefCell<Option<Gc<String>>> = const { RefCell::new(None) });
+
+#[derive(Trace)]
+struct Owner(GcCell<Option<Gc<String>>>);
+
+impl Finalize for Owner {
+    fn finalize(&self) {
+        let value = self.0.borrow_mut().take();
+        RESURRECTED.with(|r| *r.borrow_mut() = value);
+    }
+}
+
+#[test]
+fn finalizer_resurrects_contents() {
+    drop(Gc::new(Owner(GcCell::new(Some(Gc::new(
+        "alive".to_owned(),
+    ))))));
+    let report = force_collect();
+    assert_eq!(report.objects_finalized, 2);
+    assert_eq!(report.objects_freed, 1);
+
+    let value = RESURRECTED.with(|r| r.borrow_mut().take()).unwrap();
+    assert_eq!(*value, "alive");
+}
+
+thread_local!(static HELD: RefCell<Option<Gc<u8>>> = const { RefCell::new(None) });
+
+#[derive(Trace)]
+struct Release;
+
+impl Finalize for Release {
+    fn finalize(&self) {
+        // Dropping the last root of another object during a collection
+        // leaves it for the next one.
+        HELD.with(|h| h.borrow_mut().take());
+    }
+}
+
+#[test]
+fn collect_until_stable_frees_released_objects() {
+    HELD.with(|h| *h.borrow_mut() = Some(Gc::new(1)));
+    drop(Gc::new(Release));
+    assert_eq!(collect_until_stable(10), 3);
+    assert_eq!(collect_until_stable(10), 1);
+
+    HELD.with(|h| *h.borrow_mut() = Some(Gc::new(1)));
+    drop(Gc::new(Release));
+    assert_eq!(collect_until_stable(1), 1);
+    assert_eq!(force_collect().objects_freed, 1);
+}
+
+thread_local!(static ALLOCATE_ON_FINALIZE: Cell<bool> = const { Cell::new(true) });
+
+#[derive(Trace)]
+struct Allocating;
+
+impl Finalize for Allocating {
+    fn finalize(&self) {
+        // Only once, so that the collection at thread exit doesn't panic.
+        if ALLOCATE_ON_FINALIZE.replace(false) {
+            let _ = Gc::new(0);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "a Gc can't be allocated while the garbage collector is running")]
+fn allocating_in_finalizer_panics() {
+    drop(Gc::new(Allocating));
+    force_collect();
+}
+
+thread_local! {
+    static RELEASED: Cell<usize> = const { Cell::new(0) };
+    static FREED: Cell<usize> = const { Cell::new(0) };
+}
+
+struct FreeCounter;
+
+impl Drop for FreeCounter {
+    fn drop(&mut self) {
+        FREED.set(FREED.get() + 1);
+    }
+}
+
+#[derive(Trace)]
+struct Resource(#[unsafe_ignore_trace] FreeCounter);
+
+impl Finalize for Resource {
+    fn finalize(&self) {
+        RELEASED.set(RELEASED.get() + 1);
+    }
+}
+
+#[test]
+fn finalize_unreachable_does_not_free() {
+    let kept = Gc::new(Resource(FreeCounter));
+    drop(Gc::new(Resource(FreeCounter)));
+
+    assert_eq!(gc::finalize_unreachable(), 1);
+    assert_eq!((RELEASED.get(), FREED.get()), (1, 0));
+    assert_eq!(gc::finalize_unreachable(), 0);
+
+    // The next collection frees it without finalizing it again.
+    force_collect();
+    assert_eq!((RELEASED.get(), FREED.get()), (1, 1));
+    drop(kept);
+}
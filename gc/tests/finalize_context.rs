@@ -0,0 +1,69 @@
+use gc::{Finalize, FinalizeContext, Gc, Trace, finalize_context, force_collect};
+use std::sync::Mutex;
+use std::thread;
+
+static SEEN: Mutex<Vec<(u32, Option<FinalizeContext>)>> = Mutex::new(Vec::new());
+
+#[derive(Trace)]
+struct Resource(u32);
+
+impl Finalize for Resource {
+    fn finalize(&self) {
+        SEEN.lock().unwrap().push((self.0, finalize_context()));
+    }
+}
+
+fn seen(id: u32) -> Vec<Option<FinalizeContext>> {
+    let seen = SEEN.lock().unwrap();
+    seen.iter().filter(|s| s.0 == id).map(|s| s.1).collect()
+}
+
+#[test]
+fn outside_collection() {
+    drop(Resource(1));
+    assert_eq!(seen(1), [None]);
+}
+
+#[test]
+fn forced_collection() {
+    drop(Gc::new(Resource(2)));
+    force_collect();
+    let context = seen(2)[0].unwrap();
+    assert!(!context.thread_exit);
+    assert!(!context.deferred);
+}
+
+#[test]
+fn thread_exit() {
+    thread::spawn(|| {
+        drop(Gc::new(Resource(3)));
+    })
+    .join()
+    .unwrap();
+    let context = seen(3)[0].unwrap();
+    assert!(context.thread_exit);
+}
+
+#[derive(Trace)]
+struct WithContext(u32);
+
+impl Finalize for WithContext {
+    fn finalize(&self) {
+        SEEN.lock().unwrap().push((self.0, None));
+    }
+
+    fn finalize_with(&self, context: FinalizeContext) {
+        SEEN.lock().unwrap().push((self.0, Some(context)));
+    }
+}
+
+#[test]
+fn finalize_with() {
+    drop(Gc::new(WithContext(4)));
+    force_collect();
+    let context = seen(4)[0].unwrap();
+    assert!(!context.thread_exit);
+
+    drop(WithContext(5));
+    assert_eq!(seen(5), [None]);
+}
@@ -8,3 +8,12 @@ fn test_into_raw() {
     let y = Gc::new(x);
     assert_eq!(**y, 22);
 }
+
+#[test]
+fn raw_pointer_keeps_allocation_alive() {
+    let x_ptr = Gc::into_raw(Gc::new(String::from("raw")));
+    gc::force_collect();
+    let x = unsafe { Gc::from_raw(x_ptr) };
+    assert_eq!(*x, "raw");
+    assert_eq!(Gc::root_count(&x), 1);
+}
@@ -1,4 +1,4 @@
-use gc::{Gc, GcCell, GcCellRefMut};
+use gc::{BorrowState, Gc, GcCell, GcCellRefMut};
 
 #[test]
 fn test_gc_cell_ref_mut_map() {
@@ -6,3 +6,21 @@ fn test_gc_cell_ref_mut_map() {
     *GcCellRefMut::map(a.borrow_mut(), |(n, _)| n) = 2;
     assert_eq!(a.borrow_mut().0, 2);
 }
+
+#[test]
+fn test_gc_cell_borrow_state() {
+    let a = Gc::new(GcCell::new(1));
+    assert!(!a.is_borrowed());
+    {
+        let _r = a.borrow();
+        assert_eq!(a.borrow_state(), BorrowState::Reading);
+        assert!(a.is_borrowed());
+        assert!(!a.is_borrowed_mut());
+    }
+    {
+        let _w = a.borrow_mut();
+        assert!(a.is_borrowed());
+        assert!(a.is_borrowed_mut());
+    }
+    assert_eq!(a.borrow_state(), BorrowState::Unused);
+}
@@ -0,0 +1,42 @@
+use gc::{Gc, GcCell, force_collect};
+
+#[test]
+fn replace_in_heap() {
+    let a = Gc::new(1);
+    let b = Gc::new(2);
+    let cell = Gc::new(GcCell::new(a.clone()));
+    assert_eq!(Gc::root_count(&a), 1);
+
+    // The value moved out becomes a root, the value moved in stops
+    // being one.
+    let old = cell.replace(b.clone());
+    assert_eq!(Gc::root_count(&a), 2);
+    assert_eq!(Gc::root_count(&b), 1);
+    drop(old);
+
+    let old = cell.replace_with(|b| Gc::new(**b + 1));
+    assert!(Gc::ptr_eq(&old, &b));
+    assert_eq!(Gc::root_count(&b), 2);
+    drop((a, b, old));
+
+    force_collect();
+    assert_eq!(**cell.borrow(), 3);
+}
+
+#[test]
+fn swap_rooted_and_unrooted() {
+    let a = Gc::new(1);
+    let b = Gc::new(2);
+    let heap = Gc::new(GcCell::new(a.clone()));
+    let stack = GcCell::new(b.clone());
+
+    heap.swap(&stack);
+    assert_eq!(Gc::root_count(&a), 2);
+    assert_eq!(Gc::root_count(&b), 1);
+    drop((a, b));
+
+    force_collect();
+    assert_eq!(**heap.borrow(), 2);
+    assert_eq!(**stack.borrow(), 1);
+    assert_eq!(*stack.into_inner(), 1);
+}
@@ -0,0 +1,61 @@
+use gc::{Finalize, Gc, GcCell, GcMap, GcSet, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    edges: GcCell<GcSet<Node>>,
+    weights: GcCell<GcMap<Node, u32>>,
+}
+
+fn node() -> Gc<Node> {
+    Gc::new(Node {
+        edges: GcCell::new(GcSet::new()),
+        weights: GcCell::new(GcMap::new()),
+    })
+}
+
+#[test]
+fn set_by_identity() {
+    let a = Gc::new(1);
+    let b = Gc::new(1);
+    let mut set: GcSet<i32> = [a.clone(), a.clone()].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert!(set.insert(b.clone()));
+    assert!(set.contains(&b));
+
+    let removed = set.remove(&a).unwrap();
+    assert!(Gc::ptr_eq(&removed, &a));
+    assert!(!set.contains(&a));
+    assert_eq!(set.iter().count(), 1);
+}
+
+#[test]
+fn map_by_identity() {
+    let a = Gc::new(1);
+    let b = Gc::new(1);
+    let mut map = GcMap::new();
+    assert_eq!(map.insert(a.clone(), "a"), None);
+    assert_eq!(map.insert(b.clone(), "b"), None);
+    assert_eq!(map.insert(a.clone(), "A"), Some("a"));
+    *map.get_mut(&b).unwrap() = "B";
+
+    assert_eq!(map.len(), 2);
+    assert_eq!((map.get(&a), map.get(&b)), (Some(&"A"), Some(&"B")));
+    assert_eq!(map.remove(&a).map(|(_, v)| v), Some("A"));
+    assert!(!map.contains_key(&a));
+}
+
+#[test]
+fn traced_inside_gc() {
+    let a = node();
+    let b = node();
+    a.edges.borrow_mut().insert(b.clone());
+    a.weights.borrow_mut().insert(b.clone(), 3);
+    b.edges.borrow_mut().insert(a.clone());
+    drop(b);
+
+    // `b` is only reachable through `a`'s set and map.
+    force_collect();
+    let b = a.edges.borrow().iter().next().unwrap().clone();
+    assert_eq!(a.weights.borrow().get(&b), Some(&3));
+    assert!(b.edges.borrow().contains(&a));
+}
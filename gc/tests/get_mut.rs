@@ -0,0 +1,75 @@
+use gc::{Finalize, Gc, GcCell, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: Option<Gc<Node>>,
+    data: u8,
+}
+
+#[test]
+fn unique() {
+    let mut x = Gc::new(String::from("hello"));
+    Gc::get_mut(&mut x).unwrap().push_str(" world");
+    assert_eq!(*x, "hello world");
+}
+
+#[test]
+fn shared() {
+    let mut x = Gc::new(1);
+    let _holder = Gc::new(x.clone());
+    assert!(Gc::get_mut(&mut x).is_none());
+}
+
+#[derive(Trace, Finalize)]
+struct Cyclic(GcCell<Option<Gc<Cyclic>>>);
+
+#[test]
+fn self_referential() {
+    let mut node = Gc::new(Cyclic(GcCell::new(None)));
+    *node.0.borrow_mut() = Some(node.clone());
+    assert!(Gc::get_mut(&mut node).is_none());
+}
+
+#[test]
+fn move_contents_out_and_collect() {
+    let mut head = Gc::new(Node {
+        next: Some(Gc::new(Node {
+            next: None,
+            data: 2,
+        })),
+        data: 1,
+    });
+
+    let mut node = Gc::get_mut(&mut head).unwrap();
+    let next = node.next.take().unwrap();
+    // The collector neither frees the contents nor reads the borrowed
+    // value.
+    force_collect();
+    node.data = 3;
+    node.next = Some(Gc::new(Node {
+        next: None,
+        data: 4,
+    }));
+    drop(node);
+
+    force_collect();
+    assert_eq!(next.data, 2);
+    assert_eq!(Gc::root_count(&next), 1);
+    assert_eq!(head.data, 3);
+    assert_eq!(head.next.as_ref().unwrap().data, 4);
+    assert_eq!(Gc::root_count(head.next.as_ref().unwrap()), 0);
+}
+
+#[test]
+fn make_mut() {
+    let mut x = Gc::new(String::from("a"));
+    let ptr = Gc::as_ptr(&x);
+    Gc::make_mut(&mut x).push('b');
+    assert!(std::ptr::eq(Gc::as_ptr(&x), ptr));
+
+    let y = x.clone();
+    Gc::make_mut(&mut x).push('c');
+    assert!(!Gc::ptr_eq(&x, &y));
+    force_collect();
+    assert_eq!((x.as_str(), y.as_str()), ("abc", "ab"));
+}
@@ -36,7 +36,10 @@ fn test_cycle() {
     }
 
     println!("Before collection: {:?}", COUNTER.with(Cell::get));
-    force_collect();
+    let report = force_collect();
+    assert_eq!(report.objects_finalized, 4);
+    assert_eq!(report.objects_freed, 4);
+    assert!(report.bytes_freed > 0);
     println!("After collection: {:?}", COUNTER.with(Cell::get));
     assert_eq!(COUNTER.with(Cell::get), 4);
 }
@@ -0,0 +1,32 @@
+#![cfg(feature = "unstable-stats")]
+
+use gc::{Finalize, Gc, Trace, force_collect, stats};
+
+#[derive(Trace, Finalize)]
+struct Wrapper {
+    data: Vec<u8>,
+    name: String,
+}
+
+#[test]
+fn counts_owned_buffers() {
+    force_collect();
+    let before = stats().bytes_allocated;
+
+    let wrapper = Wrapper {
+        data: vec![0; 1 << 20],
+        name: String::from("wrapper"),
+    };
+    assert_eq!(wrapper.heap_size(), (1 << 20) + wrapper.name.capacity());
+    let x = Gc::new(wrapper);
+    assert!(stats().bytes_allocated - before > 1 << 20);
+
+    drop(x);
+    force_collect();
+    assert_eq!(stats().bytes_allocated, before);
+
+    let y = Gc::new(vec![String::from("a"); 4]);
+    assert!(stats().bytes_allocated - before >= 4 * size_of::<String>() + 4);
+    Gc::try_unwrap(y).unwrap();
+    assert_eq!(stats().bytes_allocated, before);
+}
@@ -0,0 +1,78 @@
+use gc::{Finalize, Gc, Trace, force_collect};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Trace, Finalize)]
+struct Mixed {
+    shared: Rc<RefCell<Vec<Gc<u32>>>>,
+}
+
+#[test]
+fn rc_refcell_contents_stay_rooted() {
+    let value = Gc::new(1);
+    let shared = Rc::new(RefCell::new(vec![value.clone()]));
+    let a = Gc::new(Mixed {
+        shared: shared.clone(),
+    });
+    let b = Gc::new(Mixed { shared });
+
+    // Moving into the heap doesn't unroot what's inside the `Rc`.
+    assert_eq!(Gc::root_count(&value), 2);
+    drop(value);
+    force_collect();
+
+    let value = a.shared.borrow_mut().pop().unwrap();
+    assert_eq!(*value, 1);
+    b.shared.borrow_mut().push(Gc::new(2));
+    force_collect();
+    assert_eq!(*a.shared.borrow()[0], 2);
+}
+
+#[derive(Trace, Finalize)]
+struct Locked {
+    current: Mutex<Gc<u32>>,
+    history: RwLock<Vec<Gc<u32>>>,
+}
+
+#[test]
+fn lock_contents_stay_rooted() {
+    let locked = Gc::new(Locked {
+        current: Mutex::new(Gc::new(1)),
+        history: RwLock::new(Vec::new()),
+    });
+
+    // Collecting while a lock is held doesn't wait for it.
+    let current = locked.current.lock().unwrap();
+    force_collect();
+    locked.history.write().unwrap().push(current.clone());
+    drop(current);
+
+    *locked.current.lock().unwrap() = Gc::new(2);
+    force_collect();
+    assert_eq!(**locked.current.lock().unwrap(), 2);
+    assert_eq!(*locked.history.read().unwrap()[0], 1);
+}
+
+#[derive(Trace, Finalize)]
+struct Config {
+    name: Cow<'static, str>,
+    shared: Arc<Vec<u32>>,
+}
+
+#[test]
+fn arc_and_cow_fields() {
+    let shared = Arc::new(vec![1, 2]);
+    let config = Gc::new(Config {
+        name: Cow::Borrowed("default"),
+        shared: shared.clone(),
+    });
+    let renamed = Gc::new(Config {
+        name: Cow::Owned(format!("{}-2", config.name)),
+        shared,
+    });
+    force_collect();
+    assert_eq!(renamed.name, "default-2");
+    assert!(Arc::ptr_eq(&config.shared, &renamed.shared));
+}
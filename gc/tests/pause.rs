@@ -0,0 +1,51 @@
+use gc::{Finalize, Gc, GcPauseGuard, Trace, disable, enable, force_collect};
+use std::cell::Cell;
+
+thread_local!(static FINALIZED: Cell<usize> = const { Cell::new(0) });
+
+#[derive(Trace)]
+struct Counted;
+
+impl Finalize for Counted {
+    fn finalize(&self) {
+        FINALIZED.with(|f| f.set(f.get() + 1));
+    }
+}
+
+fn allocate_garbage() {
+    for _ in 0..100 {
+        drop(Gc::new(Counted));
+    }
+}
+
+#[test]
+fn disable_enable() {
+    disable();
+    allocate_garbage();
+    assert_eq!(FINALIZED.with(Cell::get), 0);
+
+    enable();
+    drop(Gc::new(0u8));
+    assert_eq!(FINALIZED.with(Cell::get), 100);
+}
+
+#[test]
+fn pause_guard() {
+    {
+        let _outer = GcPauseGuard::new();
+        {
+            let _inner = GcPauseGuard::new();
+            allocate_garbage();
+        }
+        allocate_garbage();
+        assert_eq!(FINALIZED.with(Cell::get), 0);
+
+        // Forced collections still run while paused.
+        force_collect();
+        assert_eq!(FINALIZED.with(Cell::get), 200);
+
+        allocate_garbage();
+    }
+    drop(Gc::new(0u8));
+    assert_eq!(FINALIZED.with(Cell::get), 300);
+}
@@ -0,0 +1,55 @@
+use gc::{Finalize, Gc, GcCell, GcProjection, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Outer {
+    name: String,
+    inner: Inner,
+}
+
+#[derive(Trace, Finalize)]
+struct Inner {
+    value: u8,
+    next: GcCell<Option<GcProjection<u8>>>,
+}
+
+fn outer(name: &str, value: u8) -> Gc<Outer> {
+    Gc::new(Outer {
+        name: name.into(),
+        inner: Inner {
+            value,
+            next: GcCell::new(None),
+        },
+    })
+}
+
+#[test]
+fn keeps_owner_alive() {
+    let a = outer("a", 1);
+    let name = Gc::map(a.clone(), |a| a.name.as_str());
+    let value = GcProjection::map(Gc::map(a.clone(), |a| &a.inner), |inner| &inner.value);
+    assert_eq!(Gc::root_count(&a), 3);
+    assert!(Gc::ptr_eq(
+        GcProjection::owner(&name),
+        GcProjection::owner(&value)
+    ));
+
+    drop(a);
+    force_collect();
+    assert_eq!(&*name, "a");
+    assert_eq!(*value.clone(), 1);
+    assert_eq!(format!("{name} {value:?}"), "a 1");
+}
+
+#[test]
+fn traced_from_heap() {
+    let a = outer("a", 1);
+    let b = outer("b", 2);
+    *a.inner.next.borrow_mut() = Some(Gc::map(b, |b| &b.inner.value));
+    force_collect();
+    assert_eq!(**a.inner.next.borrow().as_ref().unwrap(), 2);
+
+    // A cycle through a projection is collected.
+    *a.inner.next.borrow_mut() = Some(Gc::map(a.clone(), |a| &a.inner.value));
+    drop(a);
+    assert_eq!(force_collect().objects_freed, 2);
+}
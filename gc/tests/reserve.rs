@@ -0,0 +1,41 @@
+use gc::{Finalize, Gc, Trace, reserve, set_threshold, threshold};
+use std::cell::Cell;
+
+thread_local!(static FINALIZED: Cell<usize> = const { Cell::new(0) });
+
+#[derive(Trace)]
+struct Counted;
+
+impl Finalize for Counted {
+    fn finalize(&self) {
+        FINALIZED.with(|f| f.set(f.get() + 1));
+    }
+}
+
+#[test]
+fn reserve_defers_collection() {
+    reserve(100_000);
+    for _ in 0..1000 {
+        drop(Gc::new(Counted));
+    }
+    assert_eq!(FINALIZED.with(Cell::get), 0);
+
+    // The reservation only covers so many bytes.
+    for _ in 0..10_000 {
+        drop(Gc::new(Counted));
+    }
+    assert!(FINALIZED.with(Cell::get) > 0);
+}
+
+#[test]
+fn set_threshold_and_reserve() {
+    set_threshold(1000);
+    assert_eq!(threshold(), 1000);
+    reserve(2000);
+    assert!(threshold() >= 2000);
+
+    // Reserving never lowers the threshold.
+    set_threshold(1 << 20);
+    reserve(10);
+    assert_eq!(threshold(), 1 << 20);
+}
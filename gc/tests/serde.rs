@@ -5,6 +5,7 @@ use serde_json::json;
 use std::collections::HashMap;
 
 type Example = Gc<HashMap<String, Gc<Vec<i32>>>>;
+type Pair = Gc<(Gc<Vec<i32>>, Gc<Vec<i32>>)>;
 
 #[test]
 fn serde_tests() {
@@ -21,3 +22,14 @@ fn serde_tests() {
     assert_eq!(serde_json::to_value(&expected).unwrap(), value);
     assert_eq!(serde_json::from_value::<Example>(value).unwrap(), expected);
 }
+
+#[test]
+fn sharing_is_not_preserved() {
+    let shared = Gc::new(vec![1, 2]);
+    let pair = Gc::new((shared.clone(), shared));
+    let json = serde_json::to_string(&pair).unwrap();
+    assert_eq!(json, "[[1,2],[1,2]]");
+
+    let pair: Pair = serde_json::from_str(&json).unwrap();
+    assert!(!Gc::ptr_eq(&pair.0, &pair.1));
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "unstable-stats")]
+
+use gc::{Finalize, Gc, GcCell, Trace, force_collect, stats};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+#[test]
+fn live_objects() {
+    force_collect();
+    let before = stats().live_objects;
+
+    {
+        let a = Gc::new(Node {
+            next: GcCell::new(None),
+        });
+        let b = Gc::new(Node {
+            next: GcCell::new(Some(a.clone())),
+        });
+        *a.next.borrow_mut() = Some(b);
+        let boxed: Gc<[u8; 4]> = Gc::from(Box::new([0; 4]));
+        assert_eq!(stats().live_objects, before + 3);
+
+        drop(boxed);
+        force_collect();
+        assert_eq!(stats().live_objects, before + 2);
+    }
+
+    force_collect();
+    assert_eq!(stats().live_objects, before);
+
+    let x = Gc::new(1);
+    assert_eq!(stats().live_objects, before + 1);
+    assert_eq!(Gc::try_unwrap(x), Ok(1));
+    assert_eq!(stats().live_objects, before);
+}
+
+#[test]
+fn peak_and_total_bytes() {
+    force_collect();
+    let before = stats();
+
+    let values: Vec<_> = (0..10).map(Gc::new).collect();
+    let allocated = stats().bytes_allocated - before.bytes_allocated;
+    assert!(allocated > 0);
+    drop(values);
+    force_collect();
+
+    let after = stats();
+    assert_eq!(after.bytes_allocated, before.bytes_allocated);
+    assert_eq!(
+        after.total_bytes_allocated,
+        before.total_bytes_allocated + allocated
+    );
+    assert!(after.peak_bytes_allocated >= before.bytes_allocated + allocated);
+}
+
+#[test]
+fn collection_time() {
+    let _values: Vec<_> = (0..1000).map(Gc::new).collect();
+    let before = stats();
+    force_collect();
+
+    let after = stats();
+    assert_eq!(
+        after.total_collection_time - before.total_collection_time,
+        after.last_collection_duration
+    );
+}
+
+#[cfg(feature = "unstable-global-stats")]
+#[test]
+fn global_stats_across_threads() {
+    use gc::global_stats;
+    use std::sync::mpsc;
+
+    // The worker never calls `global_stats` itself.
+    let (collected_tx, collected_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let worker = std::thread::spawn(move || {
+        let _value = Gc::new(1);
+        drop(Gc::new(2));
+        force_collect();
+        collected_tx
+            .send((std::thread::current().id(), stats()))
+            .unwrap();
+        done_rx.recv().unwrap();
+    });
+    let (id, worker_stats) = collected_rx.recv().unwrap();
+    let published = &global_stats()[&id];
+    assert_eq!(
+        published.collections_performed,
+        worker_stats.collections_performed
+    );
+    assert_eq!(published.live_objects, worker_stats.live_objects);
+    done_tx.send(()).unwrap();
+    worker.join().unwrap();
+    assert!(!global_stats().contains_key(&id));
+}
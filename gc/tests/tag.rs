@@ -0,0 +1,33 @@
+use gc::{Finalize, Gc, GcCell, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+}
+
+#[test]
+fn tag_is_kept_by_collector() {
+    let a = Gc::new(Node {
+        next: GcCell::new(None),
+    });
+    let b = Gc::new(Node {
+        next: GcCell::new(Some(a.clone())),
+    });
+    *a.next.borrow_mut() = Some(b.clone());
+    Gc::set_tag(&a, 0xff);
+    Gc::set_tag(&b, 0x5a);
+
+    let clones: Vec<_> = (0..100).map(|_| a.clone()).collect();
+    assert_eq!(Gc::root_count(&a), 101);
+    drop(b);
+    force_collect();
+    drop(clones);
+    force_collect();
+
+    assert_eq!(Gc::root_count(&a), 1);
+    assert_eq!(Gc::tag(&a), 0xff);
+    let b = a.next.borrow().clone().unwrap();
+    assert_eq!(Gc::tag(&b), 0x5a);
+    Gc::set_tag(&b, 0);
+    assert_eq!(Gc::tag(&b), 0);
+}
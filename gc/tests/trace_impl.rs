@@ -1,6 +1,11 @@
-use gc::{Finalize, Trace};
-use std::cell::RefCell;
+use gc::{Finalize, Gc, Trace};
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::AtomicUsize;
+use std::time::{Duration, Instant};
 
 thread_local!(static X: RefCell<u8> = const { RefCell::new(0) });
 
@@ -72,3 +77,38 @@ fn test() {
     unsafe { baz.trace() };
     X.with(|x| assert!(*x.borrow() == 3));
 }
+
+#[derive(Trace, Finalize)]
+struct Primitives {
+    marker: PhantomData<Rc<u8>>,
+    counter: Cell<u32>,
+    elapsed: Duration,
+    started: Instant,
+    addr: IpAddr,
+    path: PathBuf,
+    hits: AtomicUsize,
+}
+
+#[test]
+fn primitives() {
+    let value = Gc::new(Primitives {
+        marker: PhantomData,
+        counter: Cell::new(1),
+        elapsed: Duration::from_secs(1),
+        started: Instant::now(),
+        addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+        path: PathBuf::from("/"),
+        hits: AtomicUsize::new(0),
+    });
+    value.counter.set(2);
+    gc::force_collect();
+    assert_eq!(value.counter.get(), 2);
+    assert_eq!(value.elapsed, Duration::from_secs(1));
+    let _ = (
+        &value.marker,
+        value.started,
+        value.addr,
+        &value.path,
+        &value.hits,
+    );
+}
@@ -0,0 +1,10 @@
+use gc::{Gc, trim};
+
+#[test]
+fn trim_collects() {
+    let values: Vec<_> = (0..1000).map(|_| Gc::new([0u8; 1024])).collect();
+    drop(values);
+    let report = trim();
+    assert_eq!(report.objects_freed, 1000);
+    assert!(report.bytes_freed >= 1000 * 1024);
+}
@@ -0,0 +1,26 @@
+use gc::{AlreadyCollecting, Finalize, Gc, Trace, force_collect, try_force_collect};
+use std::cell::Cell;
+
+thread_local!(static RESULT: Cell<Option<Result<(), AlreadyCollecting>>> = const { Cell::new(None) });
+
+struct CollectOnDrop;
+
+impl Drop for CollectOnDrop {
+    fn drop(&mut self) {
+        RESULT.set(Some(try_force_collect().map(|_| ())));
+    }
+}
+
+#[derive(Trace, Finalize)]
+struct Holder(#[unsafe_ignore_trace] CollectOnDrop);
+
+#[test]
+fn try_force_collect_while_collecting() {
+    drop(Gc::new(Holder(CollectOnDrop)));
+    force_collect();
+    assert_eq!(RESULT.get(), Some(Err(AlreadyCollecting)));
+
+    drop(Gc::new(1));
+    let report = try_force_collect().unwrap();
+    assert_eq!(report.objects_freed, 1);
+}
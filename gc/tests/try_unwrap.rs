@@ -0,0 +1,61 @@
+use gc::{Finalize, Gc, GcCell, Trace, force_collect};
+
+#[derive(Trace, Finalize)]
+struct Node {
+    next: GcCell<Option<Gc<Node>>>,
+    data: u8,
+}
+
+#[test]
+fn unique() {
+    let x = Gc::new(String::from("hello"));
+    assert_eq!(Gc::try_unwrap(x).unwrap(), "hello");
+}
+
+#[test]
+fn shared_handle() {
+    let x = Gc::new(1);
+    let y = x.clone();
+    let x = Gc::try_unwrap(x).unwrap_err();
+    drop(y);
+    assert_eq!(Gc::try_unwrap(x), Ok(1));
+}
+
+#[test]
+fn referenced_from_heap() {
+    let x = Gc::new(2);
+    let holder = Gc::new(x.clone());
+    let x = Gc::try_unwrap(x).unwrap_err();
+    assert!(Gc::ptr_eq(&x, &*holder));
+}
+
+#[test]
+fn referenced_from_self() {
+    let node = Gc::new(Node {
+        next: GcCell::new(None),
+        data: 3,
+    });
+    *node.next.borrow_mut() = Some(node.clone());
+    let Err(node) = Gc::try_unwrap(node) else {
+        panic!("node refers to itself");
+    };
+
+    *node.next.borrow_mut() = None;
+    let node = Gc::try_unwrap(node).unwrap_or_else(|_| panic!("node is unique"));
+    assert_eq!(node.data, 3);
+}
+
+#[test]
+fn unwrapped_contents_stay_rooted() {
+    let inner = Gc::new(Node {
+        next: GcCell::new(None),
+        data: 4,
+    });
+    let outer = Gc::new(Node {
+        next: GcCell::new(Some(inner)),
+        data: 5,
+    });
+    let outer = Gc::try_unwrap(outer).unwrap_or_else(|_| panic!("outer is unique"));
+    force_collect();
+    assert_eq!(outer.next.borrow().as_ref().unwrap().data, 4);
+}
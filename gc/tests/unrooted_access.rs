@@ -0,0 +1,61 @@
+use gc::{Finalize, Gc, Trace, custom_trace, force_collect};
+use std::cell::Cell;
+
+struct PeekOnDrop {
+    target: Gc<i32>,
+}
+
+impl Finalize for PeekOnDrop {}
+
+unsafe impl Trace for PeekOnDrop {
+    custom_trace!(this, mark(&this.target));
+}
+
+impl Drop for PeekOnDrop {
+    fn drop(&mut self) {
+        let _ = *self.target;
+    }
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(expected = "Gc<i32> dereferenced while the collector was dropping a \
+                    `unrooted_access::PeekOnDrop`")
+)]
+#[cfg_attr(
+    not(debug_assertions),
+    should_panic(expected = "Gc<i32> dereferenced while the collector was dropping garbage")
+)]
+fn deref_in_drop_names_types() {
+    drop(Gc::new(PeekOnDrop { target: Gc::new(1) }));
+    force_collect();
+}
+
+thread_local!(static PEEKED: Cell<Option<Option<i32>>> = const { Cell::new(None) });
+
+struct TryPeekOnDrop {
+    target: Gc<i32>,
+}
+
+impl Finalize for TryPeekOnDrop {}
+
+unsafe impl Trace for TryPeekOnDrop {
+    custom_trace!(this, mark(&this.target));
+}
+
+impl Drop for TryPeekOnDrop {
+    fn drop(&mut self) {
+        PEEKED.set(Some(Gc::try_deref(&self.target).copied()));
+    }
+}
+
+#[test]
+fn try_deref_in_drop() {
+    drop(Gc::new(TryPeekOnDrop { target: Gc::new(1) }));
+    force_collect();
+    assert_eq!(PEEKED.get(), Some(None));
+
+    drop(TryPeekOnDrop { target: Gc::new(2) });
+    assert_eq!(PEEKED.get(), Some(Some(2)));
+}
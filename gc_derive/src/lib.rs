@@ -11,6 +11,10 @@ fn derive_trace(mut s: Structure<'_>) -> proc_macro2::TokenStream {
             .any(|attr| attr.path().is_ident("unsafe_ignore_trace"))
     });
     let trace_body = s.each(|bi| quote!(mark(#bi)));
+    let heap_size_body = s.fold(
+        quote!(0),
+        |acc, bi| quote!(#acc + ::gc::Trace::heap_size(#bi)),
+    );
 
     s.add_bounds(AddBounds::Fields);
     let trace_impl = s.unsafe_bound_impl(
@@ -41,7 +45,7 @@ fn derive_trace(mut s: Structure<'_>) -> proc_macro2::TokenStream {
                 match *self { #trace_body }
             }
             #[inline] fn finalize_glue(&self) {
-                ::gc::Finalize::finalize(self);
+                ::gc::finalize_in_glue(self);
                 #[allow(dead_code)]
                 #[inline]
                 fn mark<T: ::gc::Trace + ?Sized>(it: &T) {
@@ -49,6 +53,9 @@ fn derive_trace(mut s: Structure<'_>) -> proc_macro2::TokenStream {
                 }
                 match *self { #trace_body }
             }
+            #[inline] fn heap_size(&self) -> usize {
+                match *self { #heap_size_body }
+            }
         },
     );
 